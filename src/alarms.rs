@@ -0,0 +1,211 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use icalendar::{Alarm, Component, Trigger};
+use serde::Deserialize;
+use crate::fasting::FastingSeason;
+use crate::feasts::{feast_rank, FeastRank, FeastType};
+
+/// What an alarm rule reminds of, with the options that apply to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlarmEvent {
+    /// A day whose feast has at least the rule's rank
+    Feast {
+        /// The lowest rank that gets the alarm (defaults to great)
+        rank: Option<FeastRank>,
+        /// Restricts the alarm to feasts of the Lord, the Theotokos or a saint
+        feast_type: Option<FeastType>,
+    },
+    /// The first day of a fasting season
+    FastingSeasonStart { season: Option<FastingSeason> },
+    /// The end of a fasting season
+    FastingSeasonEnd { season: Option<FastingSeason> },
+}
+
+/// A length of time written like "6h", "1d" or "1w2d", such as how long before the day an alarm goes off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct LeadTime(pub Duration);
+
+impl TryFrom<String> for LeadTime {
+    type Error = anyhow::Error;
+
+    fn try_from(text: String) -> Result<Self> {
+        let mut total = Duration::zero();
+        let mut number = String::new();
+        for c in text.trim().chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            let amount: i64 = number.parse()
+                .map_err(|_| anyhow::anyhow!("Invalid lead time: {:?} (expected e.g. \"6h\" or \"1d\")", text))?;
+            total += match c {
+                'w' => Duration::weeks(amount),
+                'd' => Duration::days(amount),
+                'h' => Duration::hours(amount),
+                'm' => Duration::minutes(amount),
+                _ => anyhow::bail!("Invalid unit {:?} in lead time {:?} (expected w, d, h or m)", c, text),
+            };
+            number.clear();
+        }
+        if !number.is_empty() || total.is_zero() {
+            anyhow::bail!("Invalid lead time: {:?} (expected e.g. \"6h\" or \"1d\")", text);
+        }
+        Ok(LeadTime(total))
+    }
+}
+
+/// A reminder rule from the `[[alarms]]` tables of the config file
+///
+/// All-day events start at midnight, so `before = "6h"` on a feast rings at 18:00
+/// the evening before, in time for the vigil.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RuleTable")]
+pub struct AlarmRule {
+    pub on: AlarmEvent,
+    pub before: LeadTime,
+    pub message: Option<String>, // Alarm text; defaults to the event's summary
+}
+
+/// An `[[alarms]]` table as written, tagged by its `on` key
+///
+/// Each kind only accepts its own options, so e.g. a `season` on a feast rule
+/// fails when the config is loaded instead of being ignored.
+#[derive(Deserialize)]
+#[serde(tag = "on", rename_all = "snake_case", deny_unknown_fields)]
+enum RuleTable {
+    Feast {
+        before: LeadTime,
+        #[serde(default)]
+        rank: Option<FeastRank>,
+        #[serde(default)]
+        feast_type: Option<FeastType>,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    FastingSeasonStart {
+        before: LeadTime,
+        #[serde(default)]
+        season: Option<FastingSeason>,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    FastingSeasonEnd {
+        before: LeadTime,
+        #[serde(default)]
+        season: Option<FastingSeason>,
+        #[serde(default)]
+        message: Option<String>,
+    },
+}
+
+impl From<RuleTable> for AlarmRule {
+    fn from(table: RuleTable) -> Self {
+        let (on, before, message) = match table {
+            RuleTable::Feast { before, rank, feast_type, message } => {
+                (AlarmEvent::Feast { rank, feast_type }, before, message)
+            }
+            RuleTable::FastingSeasonStart { before, season, message } => {
+                (AlarmEvent::FastingSeasonStart { season }, before, message)
+            }
+            RuleTable::FastingSeasonEnd { before, season, message } => {
+                (AlarmEvent::FastingSeasonEnd { season }, before, message)
+            }
+        };
+        AlarmRule { on, before, message }
+    }
+}
+
+impl AlarmRule {
+    /// Returns true if the rule applies to the feast celebrated on `date`
+    pub fn matches_feast(&self, date: NaiveDate) -> bool {
+        let AlarmEvent::Feast { rank, feast_type } = self.on else {
+            return false;
+        };
+        feast_rank(date).is_some_and(|(day_rank, day_type)| {
+            day_rank >= rank.unwrap_or(FeastRank::Great)
+                && feast_type.is_none_or(|wanted| wanted == day_type)
+        })
+    }
+
+    /// Returns true if the rule applies to the given fasting season
+    pub fn matches_season(&self, season: FastingSeason) -> bool {
+        match self.on {
+            AlarmEvent::FastingSeasonStart { season: wanted } | AlarmEvent::FastingSeasonEnd { season: wanted } => {
+                wanted.is_none_or(|wanted| wanted == season)
+            }
+            AlarmEvent::Feast { .. } => false,
+        }
+    }
+
+    /// Builds the VALARM for an event
+    ///
+    /// The UID and DTSTAMP are set explicitly so regenerated files stay identical.
+    pub fn build(&self, summary: &str, uid: &str, modified: DateTime<Utc>) -> Alarm {
+        let trigger = match self.on {
+            AlarmEvent::FastingSeasonEnd { .. } => Trigger::before_end(self.before.0),
+            _ => Trigger::before_start(self.before.0),
+        };
+        let mut alarm = Alarm::display(self.message.as_deref().unwrap_or(summary), trigger);
+        alarm.add_property("UID", uid);
+        alarm.timestamp(modified);
+        alarm.done()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(toml: &str) -> AlarmRule {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn vigil_rank_feasts_get_the_alarm() {
+        let vigil = rule("on = \"feast\"\nrank = \"vigil\"\nbefore = \"6h\"\nmessage = \"Vigil tonight\"");
+        assert!(vigil.matches_feast(date(2025, 12, 19))); // St. Nicholas (Julian December 6)
+        assert!(vigil.matches_feast(date(2025, 10, 14))); // Protection of the Theotokos
+        assert!(vigil.matches_feast(date(2025, 1, 7)));   // Nativity of Christ outranks a vigil
+        assert!(!vigil.matches_feast(date(2025, 12, 18))); // An ordinary Thursday
+        assert!(!vigil.matches_feast(date(2025, 12, 21))); // An ordinary Sunday
+
+        let great = rule("on = \"feast\"\nbefore = \"6h\"");
+        assert!(!great.matches_feast(date(2025, 12, 19)));
+        assert!(great.matches_feast(date(2025, 1, 7)));
+    }
+
+    #[test]
+    fn feast_type_restricts_vigil_feasts() {
+        let theotokos = rule("on = \"feast\"\nrank = \"vigil\"\nfeast_type = \"theotokos\"\nbefore = \"1d\"");
+        assert!(theotokos.matches_feast(date(2025, 10, 14)));
+        assert!(!theotokos.matches_feast(date(2025, 12, 19)));
+    }
+
+    #[test]
+    fn season_rules_match_their_season() {
+        let lent = rule("on = \"fasting_season_end\"\nseason = \"great_lent\"\nbefore = \"1d\"");
+        assert!(lent.matches_season(FastingSeason::GreatLent));
+        assert!(!lent.matches_season(FastingSeason::NativityFast));
+        assert!(!lent.matches_feast(date(2025, 1, 7)));
+
+        let every = rule("on = \"fasting_season_start\"\nbefore = \"1w\"");
+        assert!(FastingSeason::ALL.into_iter().all(|season| every.matches_season(season)));
+    }
+
+    #[test]
+    fn options_of_another_kind_are_rejected() {
+        for toml in [
+            "on = \"feast\"\nseason = \"great_lent\"\nbefore = \"1d\"",
+            "on = \"fasting_season_start\"\nrank = \"vigil\"\nbefore = \"1d\"",
+            "on = \"fasting_season_end\"\nfeast_type = \"lord\"\nbefore = \"1d\"",
+            "on = \"feast\"\nbefore = \"1d\"\nrnak = \"vigil\"",
+        ] {
+            assert!(toml::from_str::<AlarmRule>(toml).is_err(), "{}", toml);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use crate::fsutil::write_atomic;
+use crate::json::{index_by_date, DataOrigin};
+use crate::schema::{parse_calendar, serialize_calendar, SCHEMA_VERSION};
+use crate::store::CalendarStore;
+
+/// Version of the bundle layout (manifest plus one calendar file per year)
+const BUNDLE_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Describes the contents of a bundle; stored as `manifest.json` in the archive
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub bundle_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub schema_version: u32, // Schema version of the calendar files in the bundle
+    pub years: Vec<BundledYear>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundledYear {
+    pub year: i32,
+    pub file: String,                      // Name of the calendar file in the archive
+    pub days: usize,
+    pub fetched_at: Option<DateTime<Utc>>, // When the exporting machine last saved the year
+    pub sha256: String,                    // Hex digest of the calendar file
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn append_file(archive: &mut tar::Builder<GzEncoder<Vec<u8>>>, name: &str, contents: &[u8], mtime: DateTime<Utc>) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime.timestamp().max(0) as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, name, contents)?;
+    Ok(())
+}
+
+/// Writes every stored year to a gzip-compressed tar archive at `path`
+pub fn export_bundle(store: &dyn CalendarStore, path: &Path) -> Result<Manifest> {
+    let created_at = Utc::now();
+    let mut files = Vec::new();
+    let mut manifest = Manifest {
+        bundle_version: BUNDLE_VERSION,
+        created_at,
+        schema_version: SCHEMA_VERSION,
+        years: Vec::new(),
+    };
+
+    for year in store.years()? {
+        let data = store.load(year)?;
+        let contents = serialize_calendar(data.values())?;
+        let file = format!("calendar_{}.json", year);
+        manifest.years.push(BundledYear {
+            year,
+            file: file.clone(),
+            days: data.len(),
+            fetched_at: store.saved_at(year)?,
+            sha256: sha256_hex(contents.as_bytes()),
+        });
+        files.push((file, contents));
+    }
+
+    // The manifest goes first so it can be read without scanning the whole archive
+    let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::best()));
+    append_file(&mut archive, MANIFEST_NAME, &serde_json::to_vec_pretty(&manifest)?, created_at)?;
+    for (file, contents) in &files {
+        append_file(&mut archive, file, contents.as_bytes(), created_at)?;
+    }
+    let bytes = archive.into_inner()?.finish()?;
+
+    write_atomic(path, &bytes)?;
+    Ok(manifest)
+}
+
+/// Reads a bundle into its manifest and files, verifying every file's checksum
+fn read_bundle(path: &Path) -> Result<(Manifest, HashMap<String, Vec<u8>>)> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open bundle: {:?}", path))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+
+    let mut files = HashMap::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+        files.insert(name, contents);
+    }
+
+    let manifest: Manifest = serde_json::from_slice(
+        files.get(MANIFEST_NAME)
+            .ok_or_else(|| anyhow::anyhow!("Bundle has no {}", MANIFEST_NAME))?
+    ).context("Malformed bundle manifest")?;
+    if manifest.bundle_version > BUNDLE_VERSION {
+        anyhow::bail!(
+            "Bundle uses version {}, but this orthoterm only supports up to version {}. Please upgrade orthoterm.",
+            manifest.bundle_version, BUNDLE_VERSION
+        );
+    }
+
+    // Verify everything before touching the store, so a corrupt bundle imports nothing
+    for bundled in &manifest.years {
+        let contents = files.get(&bundled.file)
+            .ok_or_else(|| anyhow::anyhow!("Bundle is missing {} for year {}", bundled.file, bundled.year))?;
+        let digest = sha256_hex(contents);
+        if digest != bundled.sha256 {
+            anyhow::bail!(
+                "Checksum mismatch for {}: manifest says {}, file has {}",
+                bundled.file, bundled.sha256, digest
+            );
+        }
+    }
+    Ok((manifest, files))
+}
+
+/// Merges every year of a bundle into the store, returning the manifest
+///
+/// Days already stored locally are kept, except computed days, which are replaced
+/// by scraped days from the bundle.
+pub fn import_bundle(store: &dyn CalendarStore, path: &Path) -> Result<Manifest> {
+    let (manifest, files) = read_bundle(path)?;
+
+    for bundled in &manifest.years {
+        let contents = std::str::from_utf8(&files[&bundled.file])
+            .with_context(|| format!("{} is not valid UTF-8", bundled.file))?;
+        let (mut days, _) = parse_calendar(contents)
+            .with_context(|| format!("Failed to parse {} from bundle", bundled.file))?;
+        for day in &mut days {
+            day.annotate();
+        }
+
+        let mut data = if store.exists(bundled.year)? {
+            store.load(bundled.year)?
+        } else {
+            Default::default()
+        };
+
+        let mut added = 0;
+        for (date, day) in index_by_date(days) {
+            let replace = data.get(&date).is_none_or(|local| {
+                local.origin == DataOrigin::Computed && day.origin == DataOrigin::Scraped
+            });
+            if replace {
+                data.insert(date, day);
+                added += 1;
+            }
+        }
+
+        if added > 0 {
+            store.save(bundled.year, &data)?;
+        }
+        println!("{}: {} of {} days imported", bundled.year, added, bundled.days);
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computed::compute_year_data;
+    use crate::store::MemoryStore;
+    use std::path::PathBuf;
+
+    fn bundle_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("orthoterm-{}-{}.tar.gz", name, std::process::id()))
+    }
+
+    fn sample_store() -> MemoryStore {
+        let store = MemoryStore::default();
+        for year in [1850, 1851] {
+            store.save(year, &compute_year_data(year).unwrap()).unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = bundle_path("round-trip");
+        let store = sample_store();
+        let exported = export_bundle(&store, &path).unwrap();
+        assert_eq!(exported.years.iter().map(|bundled| bundled.days).collect::<Vec<_>>(), [365, 365]);
+
+        let imported = MemoryStore::default();
+        import_bundle(&imported, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(imported.years().unwrap(), [1850, 1851]);
+        for year in [1850, 1851] {
+            assert_eq!(
+                serialize_calendar(imported.load(year).unwrap().values()).unwrap(),
+                serialize_calendar(store.load(year).unwrap().values()).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn tampered_bundle_imports_nothing() {
+        let path = bundle_path("tampered");
+        export_bundle(&sample_store(), &path).unwrap();
+
+        // Rebuild the archive with one day of 1851 changed but the original manifest
+        let (_, mut files) = read_bundle(&path).unwrap();
+        let contents = String::from_utf8(files.remove("calendar_1851.json").unwrap()).unwrap();
+        files.insert("calendar_1851.json".to_string(), contents.replacen("Holy Pascha", "Holy Pasha", 1).into_bytes());
+        let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for name in [MANIFEST_NAME, "calendar_1850.json", "calendar_1851.json"] {
+            append_file(&mut archive, name, &files[name], Utc::now()).unwrap();
+        }
+        std::fs::write(&path, archive.into_inner().unwrap().finish().unwrap()).unwrap();
+
+        let store = MemoryStore::default();
+        let error = import_bundle(&store, &path).unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.contains("Checksum mismatch for calendar_1851.json"), "{}", error);
+        assert_eq!(store.years().unwrap(), Vec::<i32>::new());
+    }
+}
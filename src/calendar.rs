@@ -1,10 +1,48 @@
 use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
 use crate::scraper::strip_html_tags;
 use scraper::{Html, Selector};
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use crate::config;
+use crate::fsutil::write_atomic;
 
 const BASE_URL: &str = "http://holytrinityorthodox.com/calendar/calendar.php";
 
-/// Fetches calendar content for a specific date and section
+/// Years the source website provides calendar data for
+pub const SCRAPED_YEARS: RangeInclusive<i32> = 1900..=2100;
+
+/// Version of the page parsers; bump it whenever parsing changes what gets stored
+///
+/// Days record the version they were parsed with, see `Provenance`.
+pub const PARSER_VERSION: u32 = 1;
+
+/// Returns the source page for a date, without any section enabled
+pub fn source_url(month: u32, day: u32, year: i32) -> String {
+    format!("{}?month={}&today={}&year={}", BASE_URL, month, day, year)
+}
+
+/// Returns the page showing only one section of a date ("dt", "header", "lives", "trp" or "scripture")
+pub fn section_url(month: u32, day: u32, year: i32, section: &str) -> Result<String> {
+    // Construct base URL with all sections disabled
+    let url = format!(
+        "{}&dt=0&header=0&lives=0&trp=0&scripture=0",
+        source_url(month, day, year)
+    );
+    
+    // Enable the requested section
+    Ok(match section {
+        "dt" => url.replace("dt=0", "dt=1"),
+        "header" => url.replace("header=0", "header=1"),
+        "lives" => url.replace("lives=0", "lives=3"),
+        "trp" => url.replace("trp=0", "trp=1"),
+        "scripture" => url.replace("scripture=0", "scripture=1"),
+        _ => anyhow::bail!("Invalid section: {}", section),
+    })
+}
+
+/// Fetches the raw page for a specific date and section and caches it
 ///
 /// # Arguments
 /// * `month` - Month (1-12)
@@ -13,8 +51,8 @@ const BASE_URL: &str = "http://holytrinityorthodox.com/calendar/calendar.php";
 /// * `section` - Section of content to fetch ("dt", "header", "lives", "trp", or "scripture")
 ///
 /// # Returns
-/// A vector of strings containing the requested content
-pub fn fetch_calendar_content(month: u32, day: u32, year: i32, section: &str) -> Result<Vec<String>> {
+/// The page's HTML, to be parsed with `parse_section`
+pub fn fetch_calendar_page(month: u32, day: u32, year: i32, section: &str) -> Result<String> {
     // Validate input parameters
     if !(1..=12).contains(&month) {
         anyhow::bail!("Invalid month: {}", month);
@@ -22,27 +60,12 @@ pub fn fetch_calendar_content(month: u32, day: u32, year: i32, section: &str) ->
     if !(1..=31).contains(&day) {
         anyhow::bail!("Invalid day: {}", day);
     }
-    if !(1900..=2100).contains(&year) {
+    if !SCRAPED_YEARS.contains(&year) {
         anyhow::bail!("Invalid year: {}", year);
     }
 
     let client = reqwest::blocking::Client::new();
-    
-    // Construct base URL with all sections disabled
-    let mut url = format!(
-        "{}?month={}&today={}&year={}&dt=0&header=0&lives=0&trp=0&scripture=0",
-        BASE_URL, month, day, year
-    );
-    
-    // Enable the requested section
-    url = match section {
-        "dt" => url.replace("dt=0", "dt=1"),
-        "header" => url.replace("header=0", "header=1"),
-        "lives" => url.replace("lives=0", "lives=3"),
-        "trp" => url.replace("trp=0", "trp=1"),
-        "scripture" => url.replace("scripture=0", "scripture=1"),
-        _ => anyhow::bail!("Invalid section: {}", section),
-    };
+    let url = section_url(month, day, year, section)?;
     
     let response = client.get(&url)
         .send()
@@ -50,14 +73,49 @@ pub fn fetch_calendar_content(month: u32, day: u32, year: i32, section: &str) ->
         .text()
         .context("Failed to get response text")?;
     
+    // Keep the raw page so a day can be re-parsed later without refetching it
+    write_atomic(&get_page_cache_path(month, day, year, section)?, response.as_bytes())?;
+    
+    Ok(response)
+}
+
+/// Reads the raw page cached when a section was last fetched
+pub fn cached_calendar_page(month: u32, day: u32, year: i32, section: &str) -> Result<String> {
+    let path = get_page_cache_path(month, day, year, section)?;
+    fs::read_to_string(&path)
+        .with_context(|| format!("No cached page: {:?}", path))
+}
+
+/// Returns when a date's cached pages were fetched
+pub fn cached_page_fetched_at(month: u32, day: u32, year: i32) -> Result<DateTime<Utc>> {
+    let path = get_page_cache_path(month, day, year, "dt")?;
+    let modified = fs::metadata(&path)
+        .with_context(|| format!("No cached page: {:?}", path))?
+        .modified()?;
+    Ok(modified.into())
+}
+
+/// Extracts a section's content from its raw page
+pub fn parse_section(section: &str, html: &str) -> Result<Vec<String>> {
     match section {
-        "trp" => parse_troparia(&response),
-        "lives" => parse_lives(&response),
-        "scripture" => parse_scripture(&response),
-        _ => Ok(vec![strip_html_tags(&response)]),
+        "trp" => parse_troparia(html),
+        "lives" => parse_lives(html),
+        "scripture" => parse_scripture(html),
+        _ => Ok(vec![strip_html_tags(html)]),
     }
 }
 
+/// Returns the path of the cached raw page for a date and section
+pub fn get_page_cache_path(month: u32, day: u32, year: i32, section: &str) -> Result<PathBuf> {
+    let mut path = config::cache_dir()?;
+    path.push("pages");
+    path.push(format!("{:04}-{:02}-{:02}", year, month, day));
+    fs::create_dir_all(&path)
+        .with_context(|| format!("Failed to create page cache directory: {:?}", path))?;
+    path.push(format!("{}.html", section));
+    Ok(path)
+}
+
 fn parse_troparia(html: &str) -> Result<Vec<String>> {
     let document = Html::parse_document(html);
     let selector = Selector::parse("p")
@@ -0,0 +1,146 @@
+use chrono::{Datelike, NaiveDate};
+use std::collections::BTreeMap;
+use std::fmt;
+use crate::json::OrthoCalendarData;
+
+/// A problem found in a stored year
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    MalformedDate(String),
+    WrongYear(NaiveDate),
+    Duplicate(NaiveDate, usize),
+    EmptySummary(NaiveDate),
+    EmptyJulianDate(NaiveDate),
+    Missing(NaiveDate),
+    Unsorted,
+}
+
+impl Problem {
+    /// Returns the date of the day that needs to be refetched or re-parsed, if any
+    pub fn bad_day(&self) -> Option<NaiveDate> {
+        match self {
+            Problem::EmptySummary(date) | Problem::EmptyJulianDate(date) | Problem::Missing(date) => Some(*date),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::MalformedDate(date) => write!(f, "{:?}: malformed date", date),
+            Problem::WrongYear(date) => write!(f, "{}: belongs to another year", date),
+            Problem::Duplicate(date, count) => write!(f, "{}: stored {} times", date, count),
+            Problem::EmptySummary(date) => write!(f, "{}: empty summary", date),
+            Problem::EmptyJulianDate(date) => write!(f, "{}: empty Julian date", date),
+            Problem::Missing(date) => write!(f, "{}: missing", date),
+            Problem::Unsorted => write!(f, "days are not in date order"),
+        }
+    }
+}
+
+/// Validates every stored day of a year
+pub fn check_year(year: i32, days: &[OrthoCalendarData]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut counts: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+
+    for day in days {
+        let Ok(date) = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d") else {
+            problems.push(Problem::MalformedDate(day.date.clone()));
+            continue;
+        };
+        if date.year() != year {
+            problems.push(Problem::WrongYear(date));
+            continue;
+        }
+
+        *counts.entry(date).or_default() += 1;
+        // A computed summary is legitimately empty when nothing is commemorated
+        if day.summary.trim().is_empty() && !day.is_computed("summary") {
+            problems.push(Problem::EmptySummary(date));
+        }
+        if day.julian_date.trim().is_empty() {
+            problems.push(Problem::EmptyJulianDate(date));
+        }
+    }
+
+    problems.extend(
+        counts.iter()
+            .filter(|(_, count)| **count > 1)
+            .map(|(date, count)| Problem::Duplicate(*date, *count))
+    );
+
+    if let Some(start) = NaiveDate::from_ymd_opt(year, 1, 1) {
+        problems.extend(
+            start.iter_days()
+                .take_while(|date| date.year() == year)
+                .filter(|date| !counts.contains_key(date))
+                .map(Problem::Missing)
+        );
+    }
+
+    if !days.windows(2).all(|pair| pair[0].date <= pair[1].date) {
+        problems.push(Problem::Unsorted);
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computed::compute_year_data;
+
+    const YEAR: i32 = 1850;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(YEAR, month, day).unwrap()
+    }
+
+    fn computed_year() -> Vec<OrthoCalendarData> {
+        compute_year_data(YEAR).unwrap().into_values().collect()
+    }
+
+    #[test]
+    fn complete_year_is_ok() {
+        assert_eq!(check_year(YEAR, &computed_year()), Vec::new());
+    }
+
+    #[test]
+    fn finds_duplicates_and_missing_days() {
+        let mut days = computed_year();
+        days.remove(40);
+        let duplicate = crate::computed::compute_day(date(3, 1)).unwrap();
+        days.insert(59, duplicate);
+
+        let problems = check_year(YEAR, &days);
+        assert!(problems.contains(&Problem::Missing(date(2, 10))), "{:?}", problems);
+        assert!(problems.contains(&Problem::Duplicate(date(3, 1), 2)), "{:?}", problems);
+        assert_eq!(Problem::Missing(date(2, 10)).bad_day(), Some(date(2, 10)));
+        assert_eq!(Problem::Duplicate(date(3, 1), 2).bad_day(), None);
+    }
+
+    #[test]
+    fn empty_summaries_only_matter_when_scraped() {
+        let mut days = computed_year();
+        days[10].summary.clear();
+        assert_eq!(check_year(YEAR, &days), Vec::new());
+
+        days[10].computed.remove("summary");
+        assert_eq!(check_year(YEAR, &days), vec![Problem::EmptySummary(date(1, 11))]);
+    }
+
+    #[test]
+    fn finds_malformed_misplaced_and_unsorted_days() {
+        let mut days = computed_year();
+        days[0].date = "1850-13-01".to_string();
+        days[1].date = "1851-01-02".to_string();
+        days.swap(5, 6);
+
+        let problems = check_year(YEAR, &days);
+        assert!(problems.contains(&Problem::MalformedDate("1850-13-01".to_string())), "{:?}", problems);
+        assert!(problems.contains(&Problem::WrongYear(NaiveDate::from_ymd_opt(1851, 1, 2).unwrap())), "{:?}", problems);
+        assert!(problems.contains(&Problem::Missing(date(1, 1))), "{:?}", problems);
+        assert!(problems.contains(&Problem::Unsorted), "{:?}", problems);
+    }
+}
@@ -0,0 +1,106 @@
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::feasts::{FeastDate, GreatFeast, GREAT_FEASTS};
+use crate::paschalion::pascha;
+
+/// A rare concurrence of a fixed Great Feast with the Paschal cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoincidenceKind {
+    Kyriopascha,
+    AnnunciationOnLazarusSaturday,
+    AnnunciationOnPalmSunday,
+    AnnunciationInHolyWeek,
+    AnnunciationInBrightWeek,
+    GreatFeastOnLentenSunday,
+    MeetingOnTriodionSunday,
+    MeetingInCheesefareWeek,
+    MeetingInGreatLent,
+}
+
+impl CoincidenceKind {
+    pub fn description(&self) -> &'static str {
+        match self {
+            CoincidenceKind::Kyriopascha => "Kyriopascha: the Annunciation falls on Pascha",
+            CoincidenceKind::AnnunciationOnLazarusSaturday => "The Annunciation falls on Lazarus Saturday",
+            CoincidenceKind::AnnunciationOnPalmSunday => "The Annunciation falls on Palm Sunday",
+            CoincidenceKind::AnnunciationInHolyWeek => "The Annunciation falls in Holy Week",
+            CoincidenceKind::AnnunciationInBrightWeek => "The Annunciation falls in Bright Week",
+            CoincidenceKind::GreatFeastOnLentenSunday => "A Great Feast falls on a Sunday of Great Lent",
+            CoincidenceKind::MeetingOnTriodionSunday => "The Meeting of the Lord falls on a pre-Lenten Sunday of the Triodion",
+            CoincidenceKind::MeetingInCheesefareWeek => "The Meeting of the Lord falls in Cheesefare week",
+            CoincidenceKind::MeetingInGreatLent => "The Meeting of the Lord falls in Great Lent",
+        }
+    }
+}
+
+impl fmt::Display for CoincidenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+/// A concurrence of a fixed Great Feast with the Paschal cycle on a given date
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Coincidence {
+    pub kind: CoincidenceKind,
+    pub feast: GreatFeast,
+}
+
+impl fmt::Display for Coincidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.kind, self.feast)
+    }
+}
+
+fn classify(feast: GreatFeast, days_from_pascha: i64) -> Option<CoincidenceKind> {
+    let lenten_sunday = matches!(days_from_pascha, -42 | -35 | -28 | -21 | -14);
+
+    match feast {
+        GreatFeast::Annunciation => match days_from_pascha {
+            0 => Some(CoincidenceKind::Kyriopascha),
+            -8 => Some(CoincidenceKind::AnnunciationOnLazarusSaturday),
+            -7 => Some(CoincidenceKind::AnnunciationOnPalmSunday),
+            -6..=-1 => Some(CoincidenceKind::AnnunciationInHolyWeek),
+            1..=6 => Some(CoincidenceKind::AnnunciationInBrightWeek),
+            _ if lenten_sunday => Some(CoincidenceKind::GreatFeastOnLentenSunday),
+            _ => None,
+        },
+        GreatFeast::Meeting => match days_from_pascha {
+            -70 | -63 | -56 | -49 => Some(CoincidenceKind::MeetingOnTriodionSunday),
+            -55..=-50 => Some(CoincidenceKind::MeetingInCheesefareWeek),
+            _ if lenten_sunday => Some(CoincidenceKind::GreatFeastOnLentenSunday),
+            -48..=-9 => Some(CoincidenceKind::MeetingInGreatLent),
+            _ => None,
+        },
+        _ if lenten_sunday => Some(CoincidenceKind::GreatFeastOnLentenSunday),
+        _ => None,
+    }
+}
+
+/// Finds every concurrence of a fixed Great Feast with the Paschal cycle in `year`
+pub fn coincidences(year: i32) -> Vec<(NaiveDate, Coincidence)> {
+    let pascha = pascha(year);
+
+    let mut found: Vec<(NaiveDate, Coincidence)> = GREAT_FEASTS.iter()
+        .filter(|definition| matches!(definition.date, FeastDate::Fixed { .. }))
+        .filter_map(|definition| {
+            let date = definition.feast.date_in(year)?;
+            let kind = classify(definition.feast, (date - pascha).num_days())?;
+            Some((date, Coincidence { kind, feast: definition.feast }))
+        })
+        .collect();
+
+    found.sort_by_key(|(date, _)| *date);
+    found
+}
+
+/// Returns the coincidences that fall on the given date
+pub fn coincidences_on(date: NaiveDate) -> Vec<Coincidence> {
+    coincidences(date.year())
+        .into_iter()
+        .filter(|(coincidence_date, _)| *coincidence_date == date)
+        .map(|(_, coincidence)| coincidence)
+        .collect()
+}
@@ -0,0 +1,85 @@
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use crate::feasts::festal_days;
+use crate::fasting::fasting_for;
+use crate::json::{CalendarDays, DataOrigin, OrthoCalendarData, TEXT_FIELDS};
+use crate::paschalion::{gregorian_to_julian, moveable_day_name, tone};
+
+/// Builds a day's data entirely from the Paschalion and the fixed calendar
+///
+/// Used for years the source website does not cover. The summary lists the
+/// moveable feast and any Great Feast of the day, the notes carry the tone and
+/// the fasting rule; lives, troparia and scripture are left empty.
+pub fn compute_day(date: NaiveDate) -> Result<OrthoCalendarData> {
+    let mut summary: Vec<String> = moveable_day_name(date)
+        .map(|name| vec![name.to_string()])
+        .unwrap_or_default();
+    summary.extend(
+        festal_days(date).iter()
+            .map(|festal| festal.to_string())
+            .filter(|name| !summary.iter().any(|existing| existing.contains(name.as_str())))
+            .collect::<Vec<_>>()
+    );
+
+    let mut notes = Vec::new();
+    if let Some(tone) = tone(date) {
+        notes.push(format!("Tone {}.", tone));
+    }
+    notes.push(format!("{}.", fasting_for(date)));
+
+    let mut data = OrthoCalendarData::new(
+        date.format("%B %-d, %Y").to_string(),
+        gregorian_to_julian(date).to_string(),
+        summary.join("; "),
+        notes.join(" "),
+        Vec::new(),
+        Vec::new(),
+        Vec::new(),
+    )?;
+    data.origin = DataOrigin::Computed;
+    data.mark_computed(&TEXT_FIELDS);
+    Ok(data)
+}
+
+/// Builds a reduced dataset for every day of the given year without scraping
+pub fn compute_year_data(year: i32) -> Result<CalendarDays> {
+    let start = NaiveDate::from_ymd_opt(year, 1, 1)
+        .ok_or_else(|| anyhow::anyhow!("Invalid year: {}", year))?;
+
+    start.iter_days()
+        .take_while(|date| date.year() == year)
+        .map(|date| Ok((date, compute_day(date)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{create_calendar_data, ANNOTATED_FIELDS};
+
+    #[test]
+    fn marks_computed_fields() {
+        let date = NaiveDate::from_ymd_opt(1850, 5, 5).unwrap();
+        let computed = compute_day(date).unwrap();
+        assert_eq!(computed.summary, "Holy Pascha");
+        for field in TEXT_FIELDS.iter().chain(&ANNOTATED_FIELDS) {
+            assert!(computed.is_computed(field), "{} should be computed", field);
+        }
+
+        let scraped = create_calendar_data(
+            "May 4, 2025".to_string(),
+            "April 21, 2025".to_string(),
+            "Third Sunday of Pascha, of the Myrrhbearing Women Tone 2.".to_string(),
+            vec!["Martyr Januarius".to_string()],
+            Vec::new(),
+            vec!["Acts 6:1-7".to_string()],
+        ).unwrap();
+        assert_eq!(scraped.origin, DataOrigin::Scraped);
+        for field in TEXT_FIELDS {
+            assert!(!scraped.is_computed(field), "{} should be scraped", field);
+        }
+        for field in ANNOTATED_FIELDS {
+            assert!(scraped.is_computed(field), "{} should be computed", field);
+        }
+    }
+}
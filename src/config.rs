@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use crate::alarms::{AlarmRule, LeadTime};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Settings read from the config file, overridden by the environment and the command line
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Where calendar data (JSON files or the SQLite database) is stored
+    pub data_dir: Option<PathBuf>,
+    /// Where raw pages fetched from the source are kept
+    pub cache_dir: Option<PathBuf>,
+    /// Where generated `.ics` files are written
+    pub ical_dir: Option<PathBuf>,
+    /// Commit every change to the data and iCal directories to a local git repository
+    pub history: bool,
+    /// Reminders attached to feasts and fasting seasons in generated iCal files
+    pub alarms: Vec<AlarmRule>,
+    /// Layouts of the SUMMARY and DESCRIPTION of each day's event
+    pub templates: Templates,
+    /// Calendar properties read by clients subscribing to the generated files
+    pub subscription: Subscription,
+    /// Years covered by the rolling iCal feed
+    pub rolling: Rolling,
+}
+
+/// Event text layouts, see `template::Template` for the syntax
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Templates {
+    pub summary: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Calendar-level properties of generated iCal files; unset ones get defaults or are left out
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Subscription {
+    /// X-WR-CALNAME, followed by the year and feed (defaults to "Orthodox Calendar")
+    pub name: Option<String>,
+    /// X-WR-CALDESC and DESCRIPTION (defaults to a description of each feed)
+    pub description: Option<String>,
+    /// X-WR-TIMEZONE, an IANA name such as "America/New_York"
+    pub timezone: Option<String>,
+    /// How often clients should check for updates: REFRESH-INTERVAL and X-PUBLISHED-TTL (defaults to 1d)
+    pub refresh_interval: Option<LeadTime>,
+    /// PRODID (defaults to orthoterm and its version)
+    pub prodid: Option<String>,
+    /// RFC 7986 COLOR, a CSS3 color name
+    pub color: Option<String>,
+    /// Where the iCal files are published; each file's SOURCE is this URL followed by its name
+    pub url: Option<String>,
+}
+
+/// The window of the rolling feed, counted from the current year
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Rolling {
+    pub years_before: u32,
+    pub years_after: u32,
+}
+
+impl Default for Rolling {
+    fn default() -> Self {
+        Self { years_before: 1, years_after: 2 }
+    }
+}
+
+pub fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|path| path.join("orthoterm").join("config.toml"))
+}
+
+impl Config {
+    /// Loads the config file
+    ///
+    /// Uses `path` if given, then `$ORTHOTERM_CONFIG`, then `~/.config/orthoterm/config.toml`.
+    /// Only an explicitly requested file has to exist.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let explicit = path.map(Path::to_path_buf)
+            .or_else(|| env::var_os("ORTHOTERM_CONFIG").map(PathBuf::from));
+        let path = match explicit.clone().or_else(default_config_path) {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+
+        if explicit.is_none() && !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file: {:?}", path))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file: {:?}", path))
+    }
+
+    /// Applies `ORTHOTERM_DATA_DIR`, `ORTHOTERM_CACHE_DIR` and `ORTHOTERM_ICAL_DIR`
+    pub fn apply_env(&mut self) {
+        let overrides = [
+            ("ORTHOTERM_DATA_DIR", &mut self.data_dir),
+            ("ORTHOTERM_CACHE_DIR", &mut self.cache_dir),
+            ("ORTHOTERM_ICAL_DIR", &mut self.ical_dir),
+        ];
+        for (name, setting) in overrides {
+            if let Some(value) = env::var_os(name).filter(|value| !value.is_empty()) {
+                *setting = Some(PathBuf::from(value));
+            }
+        }
+    }
+}
+
+/// Makes `config` the configuration for the rest of the run
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+fn resolve_dir(configured: Option<&PathBuf>, base: Option<PathBuf>, default_subdir: &[&str], setting: &str) -> Result<PathBuf> {
+    let path = match configured {
+        Some(path) => path.clone(),
+        None => {
+            let mut path = base.ok_or_else(|| {
+                anyhow::anyhow!("Could not determine a default location; set {} in the config file", setting)
+            })?;
+            path.push("orthoterm");
+            path.extend(default_subdir);
+            path
+        }
+    };
+
+    fs::create_dir_all(&path)
+        .with_context(|| format!("Failed to create {} {:?}", setting, path))?;
+    Ok(path)
+}
+
+pub fn data_dir() -> Result<PathBuf> {
+    resolve_dir(config().data_dir.as_ref(), dirs::data_local_dir(), &["data"], "data_dir")
+}
+
+pub fn cache_dir() -> Result<PathBuf> {
+    resolve_dir(config().cache_dir.as_ref(), dirs::cache_dir(), &[], "cache_dir")
+}
+
+pub fn ical_dir() -> Result<PathBuf> {
+    resolve_dir(config().ical_dir.as_ref(), dirs::data_local_dir(), &["ical"], "ical_dir")
+}
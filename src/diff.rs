@@ -0,0 +1,153 @@
+use chrono::NaiveDate;
+use std::fmt;
+use crate::json::OrthoCalendarData;
+
+/// How one scraped field differs between the stored and the refetched day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldChange {
+    Text { old: String, new: String },
+    List { removed: Vec<String>, added: Vec<String> }, // Both empty when only the order changed
+}
+
+/// The differences between a stored day and the same day refetched from the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayDiff {
+    pub date: NaiveDate,
+    pub missing: bool, // The day was not stored at all
+    pub changes: Vec<(&'static str, FieldChange)>,
+}
+
+impl DayDiff {
+    pub fn is_empty(&self) -> bool {
+        !self.missing && self.changes.is_empty()
+    }
+}
+
+fn diff_text(field: &'static str, old: &str, new: &str, changes: &mut Vec<(&'static str, FieldChange)>) {
+    if old != new {
+        changes.push((field, FieldChange::Text { old: old.to_string(), new: new.to_string() }));
+    }
+}
+
+fn diff_list(field: &'static str, old: &[String], new: &[String], changes: &mut Vec<(&'static str, FieldChange)>) {
+    if old != new {
+        changes.push((field, FieldChange::List {
+            removed: old.iter().filter(|item| !new.contains(item)).cloned().collect(),
+            added: new.iter().filter(|item| !old.contains(item)).cloned().collect(),
+        }));
+    }
+}
+
+/// Compares the scraped fields of a stored day with a freshly fetched one
+///
+/// Computed fields (liturgy, fasting, tone, ...) and provenance are not compared.
+pub fn diff_day(date: NaiveDate, stored: Option<&OrthoCalendarData>, fetched: &OrthoCalendarData) -> DayDiff {
+    let Some(stored) = stored else {
+        return DayDiff { date, missing: true, changes: Vec::new() };
+    };
+
+    let mut changes = Vec::new();
+    diff_text("julian_date", &stored.julian_date, &fetched.julian_date, &mut changes);
+    diff_text("summary", &stored.summary, &fetched.summary, &mut changes);
+    diff_text("liturgical_notes", &stored.liturgical_notes, &fetched.liturgical_notes, &mut changes);
+    diff_list("lives", &stored.lives, &fetched.lives, &mut changes);
+    diff_list("troparia", &stored.troparia, &fetched.troparia, &mut changes);
+    diff_list("scripture", &stored.scripture, &fetched.scripture, &mut changes);
+    DayDiff { date, missing: false, changes }
+}
+
+impl fmt::Display for DayDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.missing {
+            return write!(f, "{}: not stored", self.date);
+        }
+
+        write!(f, "{}:", self.date)?;
+        for (field, change) in &self.changes {
+            write!(f, "\n  {}:", field)?;
+            match change {
+                FieldChange::Text { old, new } => {
+                    write!(f, "\n    - {}\n    + {}", old, new)?;
+                }
+                FieldChange::List { removed, added } if removed.is_empty() && added.is_empty() => {
+                    write!(f, " reordered")?;
+                }
+                FieldChange::List { removed, added } => {
+                    for item in removed {
+                        write!(f, "\n    - {}", item)?;
+                    }
+                    for item in added {
+                        write!(f, "\n    + {}", item)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::create_calendar_data;
+
+    fn day(summary: &str, lives: &[&str]) -> OrthoCalendarData {
+        create_calendar_data(
+            "January 7, 2025".to_string(),
+            "December 25, 2024".to_string(),
+            summary.to_string(),
+            lives.iter().map(ToString::to_string).collect(),
+            Vec::new(),
+            vec!["Matthew 2:1-12".to_string()],
+        ).unwrap()
+    }
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, 7).unwrap()
+    }
+
+    #[test]
+    fn unchanged_day() {
+        let stored = day("Nativity of Christ", &["Adoration of the Magi"]);
+        let diff = diff_day(date(), Some(&stored), &day("Nativity of Christ", &["Adoration of the Magi"]));
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn changed_fields() {
+        let stored = day("Nativity of Christ", &["Adoration of the Magi", "Shepherds"]);
+        let fetched = day("The Nativity of Christ", &["Adoration of the Magi", "Holy Innocents"]);
+        let diff = diff_day(date(), Some(&stored), &fetched);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.changes, vec![
+            ("summary", FieldChange::Text { old: "Nativity of Christ".to_string(), new: "The Nativity of Christ".to_string() }),
+            ("lives", FieldChange::List { removed: vec!["Shepherds".to_string()], added: vec!["Holy Innocents".to_string()] }),
+        ]);
+        assert_eq!(
+            diff.to_string(),
+            "2025-01-07:\n  summary:\n    - Nativity of Christ\n    + The Nativity of Christ\n  lives:\n    - Shepherds\n    + Holy Innocents"
+        );
+    }
+
+    #[test]
+    fn reordered_list() {
+        let stored = day("Nativity of Christ", &["A", "B"]);
+        let diff = diff_day(date(), Some(&stored), &day("Nativity of Christ", &["B", "A"]));
+        assert_eq!(diff.changes, vec![("lives", FieldChange::List { removed: Vec::new(), added: Vec::new() })]);
+        assert_eq!(diff.to_string(), "2025-01-07:\n  lives: reordered");
+    }
+
+    #[test]
+    fn added_and_removed_days() {
+        let fetched = day("Nativity of Christ", &[]);
+        let added = diff_day(date(), None, &fetched);
+        assert!(added.missing && !added.is_empty());
+        assert_eq!(added.to_string(), "2025-01-07: not stored");
+
+        // A day whose summary and lives were removed at the source
+        let stored = day("Nativity of Christ", &["Adoration of the Magi"]);
+        let removed = diff_day(date(), Some(&stored), &day("", &[]));
+        let fields: Vec<&str> = removed.changes.iter().map(|(field, _)| *field).collect();
+        assert_eq!(fields, ["summary", "lives"]);
+    }
+}
@@ -0,0 +1,229 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::feasts::{festal_days, FestalRelation};
+use crate::paschalion::{days_from_pascha, gregorian_to_julian, julian_to_gregorian, pascha, JulianDate};
+
+/// What may be eaten on a given day, from least to most strict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FastingLevel {
+    FastFree,
+    NoFast,
+    Dairy,
+    Fish,
+    WineAndOil,
+    Strict,
+}
+
+impl FastingLevel {
+    pub fn description(&self) -> &'static str {
+        match self {
+            FastingLevel::FastFree => "Fast-free",
+            FastingLevel::NoFast => "No fast",
+            FastingLevel::Dairy => "Meat excluded (dairy, eggs and fish allowed)",
+            FastingLevel::Fish => "Fast: fish, wine and oil allowed",
+            FastingLevel::WineAndOil => "Fast: wine and oil allowed",
+            FastingLevel::Strict => "Strict fast",
+        }
+    }
+}
+
+impl fmt::Display for FastingLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+/// The four multi-day fasting seasons of the church year
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FastingSeason {
+    NativityFast,
+    GreatLent,
+    ApostlesFast,
+    DormitionFast,
+}
+
+impl FastingSeason {
+    pub const ALL: [FastingSeason; 4] = [
+        FastingSeason::NativityFast,
+        FastingSeason::GreatLent,
+        FastingSeason::ApostlesFast,
+        FastingSeason::DormitionFast,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FastingSeason::NativityFast => "Nativity Fast",
+            FastingSeason::GreatLent => "Great Lent",
+            FastingSeason::ApostlesFast => "Apostles' Fast",
+            FastingSeason::DormitionFast => "Dormition Fast",
+        }
+    }
+
+    /// Returns the first and last day of the season that begins in the given (Julian) year
+    ///
+    /// The Apostles' Fast is skipped in years when Pascha is so late that it has no days.
+    pub fn span(&self, year: i32) -> Option<(NaiveDate, NaiveDate)> {
+        let span = match self {
+            FastingSeason::NativityFast => {
+                (julian_to_gregorian(year, 11, 15)?, julian_to_gregorian(year, 12, 24)?)
+            }
+            FastingSeason::GreatLent => {
+                let pascha = pascha(year);
+                (pascha - Duration::days(48), pascha - Duration::days(1))
+            }
+            FastingSeason::ApostlesFast => {
+                (pascha(year) + Duration::days(57), julian_to_gregorian(year, 6, 28)?)
+            }
+            FastingSeason::DormitionFast => {
+                (julian_to_gregorian(year, 8, 1)?, julian_to_gregorian(year, 8, 14)?)
+            }
+        };
+        (span.0 <= span.1).then_some(span)
+    }
+}
+
+impl fmt::Display for FastingSeason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Returns the fasting seasons overlapping the given Gregorian year, by start date
+///
+/// Includes the Nativity Fast that began in the previous year and ends in January.
+pub fn fasting_seasons(year: i32) -> Vec<(FastingSeason, NaiveDate, NaiveDate)> {
+    let mut seasons: Vec<_> = [year - 1, year].into_iter()
+        .flat_map(|season_year| {
+            FastingSeason::ALL.into_iter()
+                .filter_map(move |season| season.span(season_year).map(|(start, end)| (season, start, end)))
+        })
+        .filter(|(_, start, end)| start.year() == year || end.year() == year)
+        .collect();
+    seasons.sort_by_key(|(_, start, _)| *start);
+    seasons
+}
+
+/// Returns the fasting season the given date falls in, if any
+pub fn fasting_season(date: NaiveDate) -> Option<FastingSeason> {
+    FastingSeason::ALL.into_iter().find(|season| {
+        [date.year() - 1, date.year()].into_iter()
+            .filter_map(|year| season.span(year))
+            .any(|(start, end)| (start..=end).contains(&date))
+    })
+}
+
+fn julian_between(julian: JulianDate, from: (u32, u32), to: (u32, u32)) -> bool {
+    (from..=to).contains(&(julian.month, julian.day))
+}
+
+/// Computes the fasting rule for the given date
+///
+/// Follows the common parish practice of the Julian calendar churches; local
+/// customs and the rank of the day's saints may relax it further.
+pub fn fasting_for(date: NaiveDate) -> FastingLevel {
+    let offset = days_from_pascha(date);
+    let weekday = date.weekday();
+    let julian = gregorian_to_julian(date);
+    let weekend = matches!(weekday, Weekday::Sat | Weekday::Sun);
+    let great_feast = festal_days(date).iter().any(|festal| festal.relation == FestalRelation::Feast);
+
+    // Fast-free weeks: Bright Week, Trinity week, the week after the Publican
+    // and the Pharisee, and the Twelve Days of Christmas
+    let svyatki = julian_between(julian, (12, 25), (12, 31)) || julian_between(julian, (1, 1), (1, 4));
+    if (0..=6).contains(&offset) || (50..=56).contains(&offset) || (-69..=-64).contains(&offset) || svyatki {
+        return FastingLevel::FastFree;
+    }
+
+    // Cheesefare week
+    if (-55..=-49).contains(&offset) {
+        return FastingLevel::Dairy;
+    }
+
+    match fasting_season(date) {
+        Some(FastingSeason::GreatLent) => {
+            let holy_week = (-6..=-1).contains(&offset);
+            return match offset {
+                -2 => FastingLevel::Strict,
+                -3 | -8 => FastingLevel::WineAndOil,
+                -7 => FastingLevel::Fish,
+                _ if julian.is(3, 25) && holy_week => FastingLevel::WineAndOil,
+                _ if julian.is(3, 25) => FastingLevel::Fish,
+                _ if holy_week => FastingLevel::Strict,
+                _ if weekend => FastingLevel::WineAndOil,
+                _ => FastingLevel::Strict,
+            };
+        }
+        Some(FastingSeason::NativityFast) => {
+            let before_forefeast = julian.month == 11 || julian.day < 20;
+            // From the forefeast on, weekends only relax the fast to wine and oil,
+            // as does a Nativity Eve falling on a weekend
+            return match weekday {
+                _ if julian.is(12, 24) && weekend => FastingLevel::WineAndOil,
+                _ if julian.is(12, 24) => FastingLevel::Strict,
+                _ if julian.is(11, 21) => FastingLevel::Fish,
+                Weekday::Sat | Weekday::Sun if before_forefeast => FastingLevel::Fish,
+                Weekday::Sat | Weekday::Sun => FastingLevel::WineAndOil,
+                Weekday::Tue | Weekday::Thu if before_forefeast => FastingLevel::Fish,
+                Weekday::Wed | Weekday::Fri => FastingLevel::Strict,
+                _ => FastingLevel::WineAndOil,
+            };
+        }
+        Some(FastingSeason::ApostlesFast) => {
+            return match weekday {
+                Weekday::Mon => FastingLevel::WineAndOil,
+                Weekday::Wed | Weekday::Fri => FastingLevel::Strict,
+                _ => FastingLevel::Fish,
+            };
+        }
+        Some(FastingSeason::DormitionFast) => {
+            return match weekday {
+                _ if julian.is(8, 6) => FastingLevel::Fish,
+                Weekday::Sat | Weekday::Sun => FastingLevel::WineAndOil,
+                _ => FastingLevel::Strict,
+            };
+        }
+        None => {}
+    }
+
+    // One-day fasts: Theophany Eve, the Beheading of the Forerunner and the Exaltation
+    if julian.is(1, 5) {
+        return FastingLevel::Strict;
+    }
+    if julian.is(8, 29) || julian.is(9, 14) {
+        return FastingLevel::WineAndOil;
+    }
+
+    // Wednesdays and Fridays, relaxed when a Great Feast falls on them
+    match weekday {
+        Weekday::Wed | Weekday::Fri if great_feast => FastingLevel::Fish,
+        Weekday::Wed | Weekday::Fri => FastingLevel::Strict,
+        _ => FastingLevel::NoFast,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn nativity_fast_weekends() {
+        assert_eq!(fasting_for(date(2025, 12, 27)), FastingLevel::Fish); // Saturday, Julian December 14
+        assert_eq!(fasting_for(date(2026, 1, 3)), FastingLevel::WineAndOil); // Saturday of the forefeast
+        assert_eq!(fasting_for(date(2026, 1, 4)), FastingLevel::WineAndOil); // Sunday of the forefeast
+        assert_eq!(fasting_for(date(2026, 1, 2)), FastingLevel::Strict); // Friday of the forefeast
+    }
+
+    #[test]
+    fn nativity_eve() {
+        assert_eq!(fasting_for(date(2025, 1, 6)), FastingLevel::Strict); // Monday
+        assert_eq!(fasting_for(date(2024, 1, 6)), FastingLevel::WineAndOil); // Saturday
+        assert_eq!(fasting_for(date(2024, 1, 7)), FastingLevel::FastFree);
+    }
+}
@@ -0,0 +1,254 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::paschalion::{days_from_pascha, gregorian_to_julian, julian_fixed_date, pascha};
+
+/// The Twelve Great Feasts of the Orthodox Church
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GreatFeast {
+    NativityOfTheotokos,
+    Exaltation,
+    EntryOfTheotokos,
+    Nativity,
+    Theophany,
+    Meeting,
+    Annunciation,
+    PalmSunday,
+    Ascension,
+    Pentecost,
+    Transfiguration,
+    Dormition,
+}
+
+/// Whether a feast celebrates the Lord, the Theotokos or a saint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeastType {
+    Lord,
+    Theotokos,
+    Saint,
+}
+
+/// The rank of a day's highest feast, in ascending order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeastRank {
+    Sunday,
+    Vigil,
+    Great,
+    Pascha,
+}
+
+/// Fixed feasts outside the Twelve that are served with an All-night Vigil, by Julian month and day
+///
+/// These carry the vigil mark (a red cross in a semicircle) in the Typikon.
+const VIGIL_FEASTS: [(u32, u32, FeastType); 6] = [
+    (1, 1, FeastType::Lord),       // Circumcision of the Lord
+    (6, 24, FeastType::Saint),     // Nativity of St. John the Baptist
+    (6, 29, FeastType::Saint),     // Holy Apostles Peter and Paul
+    (8, 29, FeastType::Saint),     // Beheading of St. John the Baptist
+    (10, 1, FeastType::Theotokos), // Protection of the Most Holy Theotokos
+    (12, 6, FeastType::Saint),     // St. Nicholas the Wonderworker
+];
+
+/// When a feast falls: a fixed Julian calendar date, or a number of days from Pascha
+#[derive(Debug, Clone, Copy)]
+pub enum FeastDate {
+    Fixed { month: u32, day: u32 },
+    Moveable { days_from_pascha: i64 },
+}
+
+/// A Great Feast together with the length of its forefeast and afterfeast
+///
+/// `afterfeast_days` counts the days after the feast up to and including the apodosis.
+#[derive(Debug, Clone, Copy)]
+pub struct FeastDefinition {
+    pub feast: GreatFeast,
+    pub name: &'static str,
+    pub date: FeastDate,
+    pub forefeast_days: u32,
+    pub afterfeast_days: u32,
+}
+
+const fn fixed(feast: GreatFeast, name: &'static str, month: u32, day: u32, forefeast_days: u32, afterfeast_days: u32) -> FeastDefinition {
+    FeastDefinition { feast, name, date: FeastDate::Fixed { month, day }, forefeast_days, afterfeast_days }
+}
+
+const fn moveable(feast: GreatFeast, name: &'static str, days_from_pascha: i64, forefeast_days: u32, afterfeast_days: u32) -> FeastDefinition {
+    FeastDefinition { feast, name, date: FeastDate::Moveable { days_from_pascha }, forefeast_days, afterfeast_days }
+}
+
+/// The Twelve Great Feasts in the order of the church year
+pub const GREAT_FEASTS: [FeastDefinition; 12] = [
+    fixed(GreatFeast::NativityOfTheotokos, "Nativity of the Most Holy Theotokos", 9, 8, 1, 4),
+    fixed(GreatFeast::Exaltation, "Exaltation of the Holy Cross", 9, 14, 1, 7),
+    fixed(GreatFeast::EntryOfTheotokos, "Entry of the Most Holy Theotokos into the Temple", 11, 21, 1, 4),
+    fixed(GreatFeast::Nativity, "Nativity of Christ", 12, 25, 5, 6),
+    fixed(GreatFeast::Theophany, "Theophany of the Lord", 1, 6, 4, 8),
+    fixed(GreatFeast::Meeting, "Meeting of the Lord", 2, 2, 1, 7),
+    fixed(GreatFeast::Annunciation, "Annunciation of the Most Holy Theotokos", 3, 25, 1, 1),
+    moveable(GreatFeast::PalmSunday, "Entry of the Lord into Jerusalem", -7, 0, 0),
+    moveable(GreatFeast::Ascension, "Ascension of the Lord", 39, 1, 8),
+    moveable(GreatFeast::Pentecost, "Holy Pentecost", 49, 0, 6),
+    fixed(GreatFeast::Transfiguration, "Transfiguration of the Lord", 8, 6, 1, 7),
+    fixed(GreatFeast::Dormition, "Dormition of the Most Holy Theotokos", 8, 15, 1, 8),
+];
+
+impl GreatFeast {
+    pub fn definition(&self) -> &'static FeastDefinition {
+        GREAT_FEASTS.iter()
+            .find(|definition| definition.feast == *self)
+            .expect("every Great Feast has a definition")
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.definition().name
+    }
+
+    /// The feast's name as it reads after "of", e.g. "the Nativity of Christ"
+    fn genitive(&self) -> String {
+        if self.name().starts_with("Holy") {
+            self.name().to_string()
+        } else {
+            format!("the {}", self.name())
+        }
+    }
+
+    pub fn feast_type(&self) -> FeastType {
+        match self {
+            GreatFeast::NativityOfTheotokos
+            | GreatFeast::EntryOfTheotokos
+            | GreatFeast::Annunciation
+            | GreatFeast::Dormition => FeastType::Theotokos,
+            _ => FeastType::Lord,
+        }
+    }
+
+    /// Returns the Gregorian date on which this feast falls in `year`
+    pub fn date_in(&self, year: i32) -> Option<NaiveDate> {
+        match self.definition().date {
+            FeastDate::Fixed { month, day } => julian_fixed_date(year, month, day),
+            FeastDate::Moveable { days_from_pascha } => Some(pascha(year) + Duration::days(days_from_pascha)),
+        }
+    }
+}
+
+impl fmt::Display for GreatFeast {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How a day relates to a Great Feast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FestalRelation {
+    Forefeast,
+    Feast,
+    Afterfeast,
+    Apodosis,
+}
+
+/// A day's place within the festal period of a Great Feast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FestalDay {
+    pub feast: GreatFeast,
+    pub relation: FestalRelation,
+}
+
+impl fmt::Display for FestalDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.relation {
+            FestalRelation::Forefeast => write!(f, "Forefeast of {}", self.feast.genitive()),
+            FestalRelation::Feast => write!(f, "{}", self.feast.name()),
+            FestalRelation::Afterfeast => write!(f, "Afterfeast of {}", self.feast.genitive()),
+            FestalRelation::Apodosis => write!(f, "Apodosis of {}", self.feast.genitive()),
+        }
+    }
+}
+
+/// The full span of a Great Feast, from the first day of the forefeast to the apodosis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FestalPeriod {
+    pub feast: GreatFeast,
+    pub start: NaiveDate,
+    pub feast_day: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl FestalPeriod {
+    fn new(feast: GreatFeast, feast_day: NaiveDate) -> Self {
+        let definition = feast.definition();
+        Self {
+            feast,
+            start: feast_day - Duration::days(definition.forefeast_days as i64),
+            feast_day,
+            end: feast_day + Duration::days(definition.afterfeast_days as i64),
+        }
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        (self.start..=self.end).contains(&date)
+    }
+
+    /// Returns how `date` relates to this period's feast, if it falls within the period
+    pub fn relation(&self, date: NaiveDate) -> Option<FestalRelation> {
+        if !self.contains(date) {
+            None
+        } else if date < self.feast_day {
+            Some(FestalRelation::Forefeast)
+        } else if date == self.feast_day {
+            Some(FestalRelation::Feast)
+        } else if date == self.end {
+            Some(FestalRelation::Apodosis)
+        } else {
+            Some(FestalRelation::Afterfeast)
+        }
+    }
+}
+
+/// Returns every festal period that overlaps the given Gregorian year, in date order
+pub fn festal_periods(year: i32) -> Vec<FestalPeriod> {
+    let mut periods: Vec<FestalPeriod> = (year - 1..=year + 1)
+        .flat_map(|feast_year| {
+            GREAT_FEASTS.iter().filter_map(move |definition| {
+                definition.feast.date_in(feast_year)
+                    .map(|feast_day| FestalPeriod::new(definition.feast, feast_day))
+            })
+        })
+        .filter(|period| period.start.year() <= year && period.end.year() >= year)
+        .collect();
+
+    periods.sort_by_key(|period| (period.start, period.feast));
+    periods.dedup();
+    periods
+}
+
+/// Returns the festal periods the given date belongs to (usually none or one)
+pub fn festal_days(date: NaiveDate) -> Vec<FestalDay> {
+    festal_periods(date.year())
+        .iter()
+        .filter_map(|period| {
+            period.relation(date).map(|relation| FestalDay { feast: period.feast, relation })
+        })
+        .collect()
+}
+
+/// Returns the rank and type of the highest feast celebrated on a date, if any
+///
+/// Pascha outranks the Great Feasts, which outrank the vigil-rank feasts and
+/// then an ordinary Sunday.
+pub fn feast_rank(date: NaiveDate) -> Option<(FeastRank, FeastType)> {
+    if days_from_pascha(date) == 0 {
+        return Some((FeastRank::Pascha, FeastType::Lord));
+    }
+    if let Some(festal_day) = festal_days(date).into_iter().find(|day| day.relation == FestalRelation::Feast) {
+        return Some((FeastRank::Great, festal_day.feast.feast_type()));
+    }
+    let julian = gregorian_to_julian(date);
+    if let Some(&(_, _, feast_type)) = VIGIL_FEASTS.iter().find(|(month, day, _)| julian.is(*month, *day)) {
+        return Some((FeastRank::Vigil, feast_type));
+    }
+    (date.weekday() == Weekday::Sun).then_some((FeastRank::Sunday, FeastType::Lord))
+}
@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LOCK_FILE_NAME: &str = ".orthoterm.lock";
+
+/// Writes `contents` to `path` atomically
+///
+/// The data goes to a temporary file in the same directory, which is synced and
+/// then renamed over the destination, so readers never see a partial file.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let dir = path.parent()
+        .ok_or_else(|| anyhow::anyhow!("Path has no parent directory: {:?}", path))?;
+    let file_name = path.file_name()
+        .ok_or_else(|| anyhow::anyhow!("Path has no file name: {:?}", path))?
+        .to_string_lossy();
+    let temp_path = dir.join(format!(".{}.{}.tmp", file_name, process::id()));
+
+    let result = (|| -> Result<()> {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result.with_context(|| format!("Failed to write {:?}", path))?;
+
+    // Persist the rename itself; not every platform allows syncing a directory
+    if let Ok(dir_handle) = File::open(dir) {
+        let _ = dir_handle.sync_all();
+    }
+    Ok(())
+}
+
+/// An advisory lock on a directory, released when dropped
+///
+/// The lock is an OS file lock (`flock` on Unix) on a file in the directory, so
+/// it is released by the kernel when its owner exits, however it exits. The file
+/// holds the owner's process id and the time it was taken, for error messages.
+pub struct DirLock {
+    file: File,
+}
+
+impl DirLock {
+    pub fn acquire(dir: &Path) -> Result<Self> {
+        let path = dir.join(LOCK_FILE_NAME);
+
+        // The file itself is never removed: a process waiting on a removed file
+        // could lock it while another locks its replacement
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)
+            .with_context(|| format!("Failed to open lock file: {:?}", path))?;
+        if let Err(e) = file.try_lock_exclusive() {
+            if e.raw_os_error() != fs2::lock_contended_error().raw_os_error() {
+                return Err(e).with_context(|| format!("Failed to lock {:?}", path));
+            }
+            let owner = fs::read_to_string(&path).unwrap_or_default();
+            anyhow::bail!("{:?} is locked by another orthoterm process ({})", dir, describe_owner(&owner));
+        }
+
+        file.set_len(0)?;
+        writeln!(file, "{} {}", process::id(), unix_now())?;
+        Ok(Self { file })
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        // Clear the owner while still holding the lock; closing the file releases it
+        let _ = self.file.set_len(0);
+    }
+}
+
+/// Locks each of the given directories once, in order
+pub fn lock_dirs(dirs: &[PathBuf]) -> Result<Vec<DirLock>> {
+    let mut locked: Vec<&PathBuf> = Vec::new();
+    let mut locks = Vec::new();
+    for dir in dirs {
+        if !locked.contains(&dir) {
+            locks.push(DirLock::acquire(dir)?);
+            locked.push(dir);
+        }
+    }
+    Ok(locks)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn parse_owner(owner: &str) -> Option<(u32, u64)> {
+    let mut parts = owner.split_whitespace();
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+fn describe_owner(owner: &str) -> String {
+    match parse_owner(owner) {
+        Some((pid, locked_at)) => format!("pid {}, locked {} seconds ago", pid, unix_now().saturating_sub(locked_at)),
+        None => "unknown owner".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_is_exclusive_until_dropped() {
+        let dir = std::env::temp_dir().join(format!("orthoterm-lock-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let lock = DirLock::acquire(&dir).unwrap();
+        let owner = fs::read_to_string(dir.join(LOCK_FILE_NAME)).unwrap();
+        assert_eq!(parse_owner(&owner).map(|(pid, _)| pid), Some(process::id()));
+        assert!(DirLock::acquire(&dir).is_err());
+
+        drop(lock);
+        assert!(DirLock::acquire(&dir).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn takes_over_a_lock_file_nobody_holds() {
+        // A lock file left behind by a process that exited is not locked
+        let dir = std::env::temp_dir().join(format!("orthoterm-stale-lock-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(LOCK_FILE_NAME), "4294967 0\n").unwrap();
+
+        assert!(DirLock::acquire(&dir).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
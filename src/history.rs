@@ -0,0 +1,155 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use crate::config;
+use crate::diff::diff_day;
+use crate::json::CalendarDays;
+
+// Files orthoterm keeps next to its data that must not be committed
+const IGNORED: &str = ".orthoterm.lock\n.*.tmp\northoterm.db*\n/ical.git/\n";
+
+// Name of the iCal history's git directory, kept in the data directory
+const ICAL_GIT_DIR: &str = "ical.git";
+
+// Days listed in a commit message before the rest are summarized
+const MAX_LISTED_DAYS: usize = 31;
+
+/// Returns true if changes should be committed to a git repository for each directory
+pub fn enabled() -> bool {
+    config::config().history
+}
+
+/// A git repository recording the history of one directory
+///
+/// The data directory's history lives in its own `.git`. The iCal directory is
+/// often served over HTTP, so its history is kept in the data directory instead
+/// and never appears among the published files.
+pub struct Repo {
+    git_dir: PathBuf,
+    work_tree: PathBuf,
+}
+
+impl Repo {
+    /// The history of the calendar files in the data directory
+    pub fn data() -> Result<Self> {
+        let work_tree = config::data_dir()?;
+        Ok(Self { git_dir: work_tree.join(".git"), work_tree })
+    }
+
+    /// The history of the iCal files, stored in the data directory
+    pub fn ical() -> Result<Self> {
+        Ok(Self { git_dir: config::data_dir()?.join(ICAL_GIT_DIR), work_tree: config::ical_dir()? })
+    }
+
+    /// Runs git on this repository and returns its standard output
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("--git-dir")
+            .arg(&self.git_dir)
+            .arg("--work-tree")
+            .arg(&self.work_tree)
+            .args(args)
+            .current_dir(&self.work_tree)
+            .output()
+            .context("Failed to run git; is it installed?")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git {} failed in {:?}: {}",
+                args.join(" "), self.work_tree, String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Creates the repository on first use, with a local identity so it works on
+    /// machines without a global git configuration
+    ///
+    /// Ignored files are listed in the repository's `info/exclude` rather than a
+    /// `.gitignore`, so nothing is added to the directory itself.
+    fn ensure(&self) -> Result<()> {
+        if self.git_dir.exists() {
+            return Ok(());
+        }
+
+        let output = Command::new("git")
+            .args(["init", "-q", "--bare"])
+            .arg(&self.git_dir)
+            .output()
+            .context("Failed to run git; is it installed?")?;
+        if !output.status.success() {
+            anyhow::bail!("git init failed for {:?}: {}", self.git_dir, String::from_utf8_lossy(&output.stderr).trim());
+        }
+        self.git(&["config", "core.bare", "false"])?;
+        self.git(&["config", "user.name", "orthoterm"])?;
+        self.git(&["config", "user.email", "orthoterm@localhost"])?;
+        fs::create_dir_all(self.git_dir.join("info"))?;
+        fs::write(self.git_dir.join("info").join("exclude"), IGNORED)?;
+        self.git(&["commit", "-q", "--allow-empty", "-m", "Start orthoterm history"])?;
+        Ok(())
+    }
+
+    /// Commits `file` if it changed, returning whether a commit was made
+    pub fn commit_file(&self, file: &str, message: &str) -> Result<bool> {
+        self.ensure()?;
+        if self.git(&["status", "--porcelain", "--", file])?.trim().is_empty() {
+            return Ok(false);
+        }
+        self.git(&["add", "--", file])?;
+        self.git(&["commit", "-q", "-m", message, "--", file])?;
+        Ok(true)
+    }
+
+    /// Returns the log of a file's past versions, newest first
+    pub fn file_log(&self, file: &str) -> Result<String> {
+        if !self.git_dir.exists() {
+            return Ok(String::new());
+        }
+        self.git(&["log", "--date=format:%Y-%m-%d %H:%M", "--format=%h  %ad  %s", "--", file])
+    }
+
+    /// Reads a file as it was at the given revision
+    pub fn file_at(&self, revision: &str, file: &str) -> Result<String> {
+        if !self.git_dir.exists() {
+            anyhow::bail!("No history for {:?}; enable it with `history = true` in the config file", self.work_tree);
+        }
+        self.git(&["show", &format!("{}:{}", revision, file)])
+    }
+}
+
+/// Builds a commit message listing the days that differ between two versions of a year
+///
+/// `action` describes why the file changed, e.g. "restored from 1a2b3c4".
+pub fn summarize_calendar_change(file: &str, action: Option<&str>, old: &CalendarDays, new: &CalendarDays) -> String {
+    let mut lines = Vec::new();
+    for (date, day) in new {
+        let diff = diff_day(*date, old.get(date), day);
+        if diff.missing {
+            lines.push(format!("{}: added", date));
+        } else if !diff.changes.is_empty() {
+            let fields: Vec<&str> = diff.changes.iter().map(|(field, _)| *field).collect();
+            lines.push(format!("{}: {}", date, fields.join(", ")));
+        } else if serde_json::to_value(day).ok() != serde_json::to_value(&old[date]).ok() {
+            lines.push(format!("{}: provenance or computed fields", date));
+        }
+    }
+    for date in old.keys().filter(|date| !new.contains_key(date)) {
+        lines.push(format!("{}: removed", date));
+    }
+
+    let mut subject = format!("{}: {} day(s) changed", file, lines.len());
+    if let Some(action) = action {
+        subject = format!("{}: {}, {} day(s) changed", file, action, lines.len());
+    }
+    if lines.is_empty() {
+        return subject;
+    }
+
+    // Keep messages for whole-year changes readable
+    if lines.len() > MAX_LISTED_DAYS {
+        let more = lines.len() - MAX_LISTED_DAYS;
+        lines.truncate(MAX_LISTED_DAYS);
+        lines.push(format!("... and {} more", more));
+    }
+    format!("{}\n\n{}", subject, lines.join("\n"))
+}
@@ -0,0 +1,112 @@
+use chrono::{Datelike, NaiveDate};
+use crate::calendar::{section_url, SCRAPED_YEARS};
+use crate::json::{DataOrigin, OrthoCalendarData};
+
+// Where scripture references link to
+const BIBLE_URL: &str = "https://www.biblegateway.com/passage/?version=NKJV&search=";
+
+/// Escapes text for use in HTML content and attribute values
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Percent-encodes a query parameter value
+fn encode_query(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            b' ' => "+".to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Returns a page of the source website for a scraped day
+fn source_section(date: NaiveDate, day: &OrthoCalendarData, section: &str) -> Option<String> {
+    if day.origin != DataOrigin::Scraped || !SCRAPED_YEARS.contains(&date.year()) {
+        return None;
+    }
+    section_url(date.month(), date.day(), date.year(), section).ok()
+}
+
+/// Turns plain text into paragraphs, with single line breaks kept as `<br>`
+pub fn paragraphs(text: &str) -> String {
+    text.split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| format!("<p>{}</p>", escape(paragraph).replace('\n', "<br>")))
+        .collect()
+}
+
+/// Renders a troparion, setting its title (e.g. "Troparion, Tone 4") in italics
+fn troparion(text: &str) -> String {
+    let text = text.strip_prefix("Troparia").map_or(text, str::trim);
+    let split = [" — ", " - ", ": "].iter()
+        .filter_map(|separator| text.split_once(separator))
+        .find(|(title, _)| title.len() <= 40 && title.contains(|c: char| c.is_alphabetic()));
+    match split {
+        Some((title, hymn)) => format!("<p><i>{}</i><br>{}</p>", escape(title.trim()), escape(hymn.trim())),
+        None => format!("<p>{}</p>", escape(text)),
+    }
+}
+
+/// The saints commemorated on a day, with a link to their lives
+pub fn saints_html(date: NaiveDate, day: &OrthoCalendarData) -> String {
+    if day.lives.is_empty() {
+        return String::new();
+    }
+    let mut html = String::from("<h3>Saints</h3><ul>");
+    for life in &day.lives {
+        html.push_str(&format!("<li>{}</li>", escape(life.trim())));
+    }
+    html.push_str("</ul>");
+    if let Some(url) = source_section(date, day, "lives") {
+        html.push_str(&format!("<p><a href=\"{}\">Lives of the saints</a></p>", escape(&url)));
+    }
+    html
+}
+
+/// The scripture readings of a day, each linked to its text
+pub fn readings_html(day: &OrthoCalendarData) -> String {
+    if day.scripture.is_empty() {
+        return String::new();
+    }
+    let mut html = String::from("<h3>Scripture</h3><ul>");
+    for reading in day.scripture.iter().map(|reading| reading.trim()) {
+        let url = format!("{}{}", BIBLE_URL, encode_query(reading));
+        html.push_str(&format!("<li><a href=\"{}\">{}</a></li>", escape(&url), escape(reading)));
+    }
+    html.push_str("</ul>");
+    html
+}
+
+/// Renders everything known about a day as an HTML fragment, for X-ALT-DESC
+pub fn day_html(date: NaiveDate, day: &OrthoCalendarData) -> String {
+    let mut html = format!("<p><i>{}</i></p>", escape(&day.julian_date));
+
+    let mut observed: Vec<String> = day.festal.iter().map(ToString::to_string).collect();
+    observed.extend(day.coincidences.iter().map(ToString::to_string));
+    observed.extend(day.observances.iter().map(ToString::to_string));
+    observed.extend(day.liturgy.map(|liturgy| liturgy.to_string()));
+    if !observed.is_empty() {
+        let lines: Vec<String> = observed.iter().map(|line| escape(line)).collect();
+        html.push_str(&format!("<p>{}</p>", lines.join("<br>")));
+    }
+
+    if !day.liturgical_notes.is_empty() {
+        html.push_str("<h3>Notes</h3>");
+        html.push_str(&paragraphs(&day.liturgical_notes));
+    }
+    html.push_str(&saints_html(date, day));
+    if !day.troparia.is_empty() {
+        html.push_str("<h3>Troparia</h3>");
+        for text in &day.troparia {
+            html.push_str(&troparion(text));
+        }
+    }
+    html.push_str(&readings_html(day));
+    html
+}
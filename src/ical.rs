@@ -1,108 +1,872 @@
-use anyhow::{anyhow, Result, Context};
-use chrono::NaiveDate;
-use icalendar::{Calendar, Event, EventLike, Component};
+use anyhow::{Result, Context};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use icalendar::{Calendar, Event, EventLike, Component, Property};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
-use dirs;
-use crate::json::OrthoCalendarData;
+use crate::json::{CalendarDays, DataOrigin, OrthoCalendarData};
+use crate::history;
+use crate::alarms::AlarmRule;
+use crate::template::{Template, DEFAULT_DESCRIPTION, DEFAULT_SUMMARY};
+use crate::fsutil::write_atomic;
+use crate::config::{self, Subscription};
+use crate::calendar::{source_url, SCRAPED_YEARS};
+use crate::feasts::{festal_periods, FestalPeriod, FestalRelation};
+use crate::fasting::{fasting_seasons, FastingLevel, FastingSeason};
+use crate::paschalion::days_from_pascha;
+use crate::observances::{observances, Observance};
+use crate::html::{day_html, paragraphs, readings_html, saints_html};
+use crate::rfc5545::{escape_text, format_duration, refold};
 
-fn sanitize_text(text: &str) -> String {
-    text.replace("\\", "")  // Remove backslashes
-        .replace("\\n", "\n")  // Replace \n with actual newlines
-        .replace("\n\n", "\n")  // Replace double newlines with single newlines
+/// A calendar file generated for each year, holding all or part of the day's content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feed {
+    /// One event per day with everything in its description, tagged with CATEGORIES
+    All,
+    /// The Twelve Great Feasts and Pascha
+    Feasts,
+    /// The saints and events commemorated each day
+    Commemorations,
+    /// The fasting rule of each fast day
+    Fasting,
+    /// Each day's scripture readings
+    Readings,
+}
+
+impl Feed {
+    pub const ALL: [Feed; 5] = [Feed::All, Feed::Feasts, Feed::Commemorations, Feed::Fasting, Feed::Readings];
+
+    pub fn parse(name: &str) -> Result<Self> {
+        Feed::ALL.into_iter()
+            .find(|feed| feed.name() == name)
+            .ok_or_else(|| anyhow::anyhow!(
+                "Unknown feed: {} (expected all, feasts, commemorations, fasting or readings)", name
+            ))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Feed::All => "all",
+            Feed::Feasts => "feasts",
+            Feed::Commemorations => "commemorations",
+            Feed::Fasting => "fasting",
+            Feed::Readings => "readings",
+        }
+    }
+
+    /// Returns true if the feed has an event for the day, without building it
+    fn has_content(&self, date: NaiveDate, day_data: &OrthoCalendarData) -> bool {
+        match self {
+            Feed::All => !day_data.summary.is_empty(),
+            Feed::Feasts => great_feast_name(date, day_data).is_some(),
+            Feed::Commemorations => !day_data.summary.is_empty() || !day_data.lives.is_empty(),
+            Feed::Fasting => day_data.fasting
+                .is_some_and(|level| !matches!(level, FastingLevel::NoFast | FastingLevel::FastFree)),
+            Feed::Readings => !day_data.scripture.is_empty(),
+        }
+    }
+
+    /// The CATEGORIES value of the feed's events
+    pub fn category(&self) -> &'static str {
+        match self {
+            Feed::All => "Orthodox Calendar",
+            Feed::Feasts => "Great Feasts",
+            Feed::Commemorations => "Commemorations",
+            Feed::Fasting => "Fasting",
+            Feed::Readings => "Readings",
+        }
+    }
+
+    /// The default X-WR-CALDESC of the feed's files
+    fn description(&self) -> &'static str {
+        match self {
+            Feed::All => "Saints, feasts, fasting and readings of each day of the Orthodox calendar",
+            Feed::Feasts => "The Twelve Great Feasts and Pascha",
+            Feed::Commemorations => "The saints and events commemorated each day",
+            Feed::Fasting => "Fasting seasons and the fasting rule of each fast day",
+            Feed::Readings => "The scripture readings of each day",
+        }
+    }
+
+    fn file_name(&self, span: &Span) -> String {
+        match (self, span) {
+            (Feed::All, Span::Year(year)) => format!("calendar_{}.ics", year),
+            (_, Span::Year(year)) => format!("calendar_{}_{}.ics", year, self.name()),
+            (Feed::All, Span::Rolling(_)) => "calendar.ics".to_string(),
+            (_, Span::Rolling(_)) => format!("calendar_{}.ics", self.name()),
+        }
+    }
+}
+
+/// The years a set of iCal files covers
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Span {
+    /// One year, named in the files' names
+    Year(i32),
+    /// The available years of a window that moves forward every January, in ascending
+    /// order, in files whose names never change
+    Rolling(Vec<i32>),
+}
+
+impl Span {
+    fn years(&self) -> Vec<i32> {
+        match self {
+            Span::Year(year) => vec![*year],
+            Span::Rolling(years) => years.clone(),
+        }
+    }
+
+    /// Returns true if `year` is the first year of the span that something running from
+    /// `start` to `end` overlaps, so seasons and periods crossing New Year are listed once
+    fn lists_in(&self, year: i32, start: NaiveDate, end: NaiveDate) -> bool {
+        self.years().into_iter().find(|listed| (start.year()..=end.year()).contains(listed)) == Some(year)
+    }
+}
+
+/// Optional content to include when generating an iCal file
+#[derive(Debug, Clone)]
+pub struct IcalOptions {
+    /// Emit one multi-day event per Great Feast, spanning forefeast to apodosis
+    pub festal_periods: bool,
+    /// Emit a separate event for each Soul Saturday and other computed observance
+    pub observances: bool,
+    /// Emit one multi-day event per fasting season in the combined feed
+    pub fasting_seasons: bool,
+    /// Emit a colored event with the fasting rule of each fast day in the combined feed
+    pub fasting_days: bool,
+    /// Which files to write
+    pub feeds: Vec<Feed>,
+    /// Reminders to attach to matching feasts and fasting seasons
+    pub alarms: Vec<AlarmRule>,
+    /// Layout of each day's SUMMARY in the combined feed
+    pub summary_template: Template,
+    /// Layout of each day's DESCRIPTION in the combined feed
+    pub description_template: Template,
+    /// Whether the description template was set in the config file, in which case
+    /// the HTML description follows it instead of the built-in layout
+    pub custom_description: bool,
+    /// Calendar-level properties for subscribers
+    pub subscription: Subscription,
+}
+
+impl Default for IcalOptions {
+    fn default() -> Self {
+        Self {
+            festal_periods: false,
+            observances: false,
+            fasting_seasons: false,
+            fasting_days: false,
+            feeds: vec![Feed::All],
+            alarms: Vec::new(),
+            summary_template: Template::parse(DEFAULT_SUMMARY).expect("default summary template is valid"),
+            description_template: Template::parse(DEFAULT_DESCRIPTION).expect("default description template is valid"),
+            custom_description: false,
+            subscription: Subscription::default(),
+        }
+    }
+}
+
+/// Normalizes scraped text before it goes into an event
+///
+/// Only line endings and surrounding whitespace are touched; backslashes, commas
+/// and semicolons are escaped when the file is written.
+pub fn sanitize_text(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
         .trim()
         .to_string()
 }
 
-fn get_ical_dir() -> PathBuf {
-    let mut path = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
-    path.push("orthoterm");
-    path.push("ical");
-    fs::create_dir_all(&path).unwrap_or_default();
-    path
+pub fn get_ical_dir() -> Result<PathBuf> {
+    config::ical_dir()
 }
 
-fn get_ical_path(year: i32) -> PathBuf {
-    let mut path = get_ical_dir();
-    path.push(format!("calendar_{}.ics", year));
-    path
+fn get_ical_path(span: &Span, feed: Feed) -> Result<PathBuf> {
+    let mut path = get_ical_dir()?;
+    path.push(feed.file_name(span));
+    Ok(path)
 }
 
-/// Checks if an iCal file exists for the specified year
-pub fn ical_exists(year: i32) -> bool {
-    get_ical_path(year).exists()
+/// Returns true if every selected feed exists and was written after the data was last saved
+pub fn ical_up_to_date(year: i32, options: &IcalOptions, data_saved: Option<DateTime<Utc>>) -> Result<bool> {
+    for &feed in &options.feeds {
+        let path = get_ical_path(&Span::Year(year), feed)?;
+        if !path.exists() {
+            return Ok(false);
+        }
+        let written: DateTime<Utc> = fs::metadata(&path)?.modified()?.into();
+        if data_saved.is_some_and(|saved| saved > written) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// Domain part of every event UID
+const UID_DOMAIN: &str = "orthoterm";
+
+/// Builds a UID from the event's date and kind, so regenerating a file keeps its events' identity
+fn event_uid(date: NaiveDate, kind: &str) -> String {
+    format!("{}-{}@{}", date.format("%Y%m%d"), kind, UID_DOMAIN)
 }
 
-pub fn generate_ical(year: i32, data: &[OrthoCalendarData]) -> Result<()> {
-    if !(1900..=2100).contains(&year) {
-        anyhow::bail!("Year {} is out of supported range", year);
+/// Returns the serde name of a unit enum in kebab-case, for use in UIDs
+fn uid_slug<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value).ok()
+        .and_then(|value| value.as_str().map(|name| name.replace('_', "-")))
+        .unwrap_or_default()
+}
+
+fn format_utc(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Sets DTSTAMP and LAST-MODIFIED
+///
+/// `EventLike::last_modified` writes the property as `LAST_MODIFIED`, so it is added by hand.
+fn stamp(event: &mut Event, modified: DateTime<Utc>) {
+    event.timestamp(modified);
+    event.add_property("LAST-MODIFIED", format_utc(modified));
+}
+
+/// Returns when the year's data last changed: the latest fetch of any of its days
+///
+/// Fully computed years have no fetch times and use the Unix epoch, so their
+/// output only changes when orthoterm itself does.
+fn data_modified(data: &CalendarDays) -> DateTime<Utc> {
+    data.values()
+        .filter_map(|day| day.provenance.as_ref().map(|provenance| provenance.fetched_at))
+        .max()
+        .unwrap_or(DateTime::UNIX_EPOCH)
+}
+
+/// Makes an event last the whole of one day
+///
+/// `EventLike::all_day` sets DTEND equal to DTSTART, which RFC 5545 forbids; DTEND
+/// is exclusive, so it is the following day.
+fn all_day(event: &mut Event, date: NaiveDate) {
+    event.starts(date);
+    event.ends(date + Duration::days(1));
+}
+
+fn festal_period_event(period: &FestalPeriod, modified: DateTime<Utc>) -> Event {
+    let mut event = Event::new();
+    event.uid(&event_uid(period.start, &format!("festal-period-{}", uid_slug(&period.feast))));
+    stamp(&mut event, modified);
+    // DTEND is exclusive for all-day events
+    event.starts(period.start);
+    event.ends(period.end + Duration::days(1));
+    event.summary(&format!("Festal period: {}", period.feast));
+
+    let description = if period.start < period.feast_day {
+        format!(
+            "Forefeast from {}\nFeast on {}\nApodosis on {}",
+            period.start, period.feast_day, period.end
+        )
+    } else {
+        format!("Feast on {}\nApodosis on {}", period.feast_day, period.end)
+    };
+    event.description(&description);
+    event.done()
+}
+
+// RFC 7986 COLOR (a CSS3 color name) of fasting events, from the strictest to the most relaxed
+const FASTING_SEASON_COLOR: &str = "purple";
+
+fn fasting_color(level: FastingLevel) -> &'static str {
+    match level {
+        FastingLevel::Strict => "purple",
+        FastingLevel::WineAndOil => "mediumpurple",
+        FastingLevel::Fish => "steelblue",
+        FastingLevel::Dairy => "khaki",
+        FastingLevel::NoFast => "lightgray",
+        FastingLevel::FastFree => "gold",
     }
+}
 
-    let path = get_ical_path(year);
-    
-    // Ensure parent directory exists
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+/// Attaches a VALARM for every rule that applies to the event
+fn add_alarms(event: &mut Event, rules: &[AlarmRule], applies: impl Fn(&AlarmRule) -> bool, modified: DateTime<Utc>) {
+    let summary = event.property_value("SUMMARY").unwrap_or_default().to_string();
+    let uid = event.property_value("UID").unwrap_or_default().to_string();
+    for (index, rule) in rules.iter().enumerate().filter(|(_, rule)| applies(rule)) {
+        event.alarm(rule.build(&summary, &format!("alarm-{}-{}", index, uid), modified));
     }
-    
-    let mut calendar = Calendar::new();
-    calendar.name(&format!("Orthodox Calendar {}", year));
-    
-    for day_data in data {
-        // Split the date string and take the Gregorian date part
-        let date_parts: Vec<&str> = day_data.date.split('/').collect();
-        let gregorian_date = date_parts.first()
-            .ok_or_else(|| anyhow!("Invalid date format: missing Gregorian date"))?
-            .trim();
-        
-        // Parse the date directly in YYYY-MM-DD format
-        let date = NaiveDate::parse_from_str(gregorian_date, "%Y-%m-%d")
-            .with_context(|| format!("Failed to parse date: {}", gregorian_date))?;
-        
-        if !day_data.summary.is_empty() {
-            let mut event = Event::new();
-            event.all_day(date);
-            
-            // Use the pre-split summary
-            event.summary(&day_data.summary);
-            
-            // Clean up troparia by removing "Troparia" prefix if present
-            let clean_troparia: Vec<String> = day_data.troparia.iter()
-                .map(|t| {
-                    if t.starts_with("Troparia") {
-                        t.replacen("Troparia", "", 1).trim().to_string()
-                    } else {
-                        t.to_string()
-                    }
-                })
-                .collect();
-
-            // Add Julian date at the top of the description
-            let description = if day_data.liturgical_notes.is_empty() {
-                format!(
-                    "({})\n\nSaints:\n{}\n\nTroparia:\n{}\n\nScripture:\n{}",
-                    day_data.julian_date,
-                    sanitize_text(&day_data.lives.join("\n")),
-                    sanitize_text(&clean_troparia.join("\n")).replace("\n", "\n\n"),  // Add extra newlines between troparia
-                    sanitize_text(&day_data.scripture.join("\n"))
-                )
-            } else {
-                format!(
-                    "({})\n\nNotes:\n{}\n\nSaints:\n{}\n\nTroparia:\n{}\n\nScripture:\n{}",
-                    day_data.julian_date,
-                    day_data.liturgical_notes,
-                    sanitize_text(&day_data.lives.join("\n")),
-                    sanitize_text(&clean_troparia.join("\n")).replace("\n", "\n\n"),  // Add extra newlines between troparia
-                    sanitize_text(&day_data.scripture.join("\n"))
-                )
-            };
-            event.description(&description);
-            
-            calendar.push(event.done());
+}
+
+fn fasting_season_event(season: FastingSeason, start: NaiveDate, end: NaiveDate, alarms: &[AlarmRule], modified: DateTime<Utc>) -> Event {
+    let mut event = Event::new();
+    event.uid(&event_uid(start, &format!("fasting-season-{}", uid_slug(&season))));
+    stamp(&mut event, modified);
+    // DTEND is exclusive for all-day events
+    event.starts(start);
+    event.ends(end + Duration::days(1));
+    event.summary(season.name());
+    event.description(&format!("From {} to {}", start, end));
+    event.add_property("COLOR", FASTING_SEASON_COLOR);
+    event.add_multi_property("CATEGORIES", Feed::Fasting.category());
+    add_alarms(&mut event, alarms, |rule| rule.matches_season(season), modified);
+    event.done()
+}
+
+fn observance_event(date: NaiveDate, observance: Observance, modified: DateTime<Utc>) -> Event {
+    let mut event = Event::new();
+    event.uid(&event_uid(date, &format!("observance-{}", uid_slug(&observance))));
+    stamp(&mut event, modified);
+    all_day(&mut event, date);
+    event.summary(observance.name());
+    if observance.is_soul_saturday() {
+        event.description("Commemoration of the departed (panikhida)");
+    }
+    event.done()
+}
+
+/// Builds the combined event for a day, carrying everything known about it
+fn day_event(date: NaiveDate, day_data: &OrthoCalendarData, options: &IcalOptions) -> Option<Event> {
+    if !Feed::All.has_content(date, day_data) {
+        return None;
+    }
+
+    let mut event = Event::new();
+    event.uid(&event_uid(date, "day"));
+    all_day(&mut event, date);
+    event.summary(&options.summary_template.render(day_data));
+    let description = options.description_template.render(day_data);
+    event.description(&description);
+    if options.custom_description {
+        alt_description(&mut event, &paragraphs(&description));
+    } else {
+        alt_description(&mut event, &day_html(date, day_data));
+    }
+
+    // Tag the event with every feed that has something for this day
+    for feed in Feed::ALL.into_iter().filter(|&feed| feed != Feed::All) {
+        if feed.has_content(date, day_data) {
+            event.add_multi_property("CATEGORIES", feed.category());
+        }
+    }
+    Some(event)
+}
+
+/// Returns the name of the Great Feast (or Pascha) celebrated on a day, if any
+fn great_feast_name(date: NaiveDate, day_data: &OrthoCalendarData) -> Option<&'static str> {
+    if days_from_pascha(date) == 0 {
+        return Some("Holy Pascha");
+    }
+    day_data.festal.iter()
+        .find(|festal_day| festal_day.relation == FestalRelation::Feast)
+        .map(|festal_day| festal_day.feast.name())
+}
+
+/// Builds the event a single-topic feed has for a day, if any
+fn feed_day_event(feed: Feed, date: NaiveDate, day_data: &OrthoCalendarData, options: &IcalOptions) -> Option<Event> {
+    if !feed.has_content(date, day_data) {
+        return None;
+    }
+
+    let (summary, description) = match feed {
+        Feed::All => return day_event(date, day_data, options),
+        Feed::Feasts => {
+            let name = great_feast_name(date, day_data)?;
+            let liturgy = day_data.liturgy.map(|liturgy| liturgy.to_string()).unwrap_or_default();
+            (name.to_string(), format!("({})\n{}", day_data.julian_date, liturgy).trim().to_string())
+        }
+        Feed::Commemorations => {
+            let mut event = single_day_event(feed, date, &day_data.summary, &sanitize_text(&day_data.lives.join("\n")));
+            alt_description(&mut event, &saints_html(date, day_data));
+            return Some(event);
+        }
+        Feed::Fasting => {
+            let level = day_data.fasting?;
+            let mut event = single_day_event(feed, date, &level.to_string(), &day_data.liturgical_notes);
+            event.add_property("COLOR", fasting_color(level));
+            return Some(event);
+        }
+        Feed::Readings => {
+            let summary = day_data.scripture.join("; ");
+            let mut event = single_day_event(feed, date, &summary, &sanitize_text(&day_data.scripture.join("\n")));
+            alt_description(&mut event, &readings_html(day_data));
+            return Some(event);
+        }
+    };
+
+    Some(single_day_event(feed, date, &summary, &description))
+}
+
+/// Adds an HTML version of the description, shown by clients that render it instead of DESCRIPTION
+fn alt_description(event: &mut Event, html: &str) {
+    if html.is_empty() {
+        return;
+    }
+    let document = format!("<html><body>{}</body></html>", html);
+    event.append_property(
+        Property::new("X-ALT-DESC", escape_text(&document))
+            .add_parameter("FMTTYPE", "text/html")
+            .done(),
+    );
+}
+
+fn single_day_event(feed: Feed, date: NaiveDate, summary: &str, description: &str) -> Event {
+    let mut event = Event::new();
+    event.uid(&event_uid(date, feed.name()));
+    all_day(&mut event, date);
+    event.summary(summary);
+    if !description.is_empty() {
+        event.description(description);
+    }
+    event.add_multi_property("CATEGORIES", feed.category());
+    event
+}
+
+fn feed_events(span: &Span, data: &CalendarDays, options: &IcalOptions, feed: Feed) -> Vec<Event> {
+    let modified = data_modified(data);
+    let mut events = Vec::new();
+
+    for (&date, day_data) in data {
+        let day_modified = day_data.provenance.as_ref().map_or(modified, |provenance| provenance.fetched_at);
+        let url = day_url(date, day_data);
+        if let Some(mut event) = feed_day_event(feed, date, day_data, options) {
+            stamp(&mut event, day_modified);
+            if let Some(url) = &url {
+                event.url(url);
+            }
+            if matches!(feed, Feed::All | Feed::Feasts) {
+                add_alarms(&mut event, &options.alarms, |rule| rule.matches_feast(date), day_modified);
+            }
+            events.push(event.done());
+        }
+        if feed == Feed::All && options.fasting_days {
+            if let Some(mut event) = feed_day_event(Feed::Fasting, date, day_data, options) {
+                stamp(&mut event, day_modified);
+                if let Some(url) = &url {
+                    event.url(url);
+                }
+                events.push(event.done());
+            }
+        }
+    }
+
+    if feed == Feed::Fasting || (feed == Feed::All && options.fasting_seasons) {
+        for year in span.years() {
+            for (season, start, end) in fasting_seasons(year) {
+                if span.lists_in(year, start, end) {
+                    events.push(fasting_season_event(season, start, end, &options.alarms, modified));
+                }
+            }
         }
     }
     
-    fs::write(&path, calendar.to_string())
+    if options.festal_periods && matches!(feed, Feed::All | Feed::Feasts) {
+        for year in span.years() {
+            for period in festal_periods(year) {
+                if span.lists_in(year, period.start, period.end) {
+                    events.push(festal_period_event(&period, modified));
+                }
+            }
+        }
+    }
+
+    if options.observances && matches!(feed, Feed::All | Feed::Commemorations) {
+        for (date, observance) in span.years().into_iter().flat_map(observances) {
+            events.push(observance_event(date, observance, modified));
+        }
+    }
+    events
+}
+
+/// Writes the selected feeds for the year, returning how many events are new or changed
+pub fn generate_ical(year: i32, data: &CalendarDays, options: &IcalOptions) -> Result<usize> {
+    write_feeds(&Span::Year(year), data, options)
+}
+
+/// Writes the selected feeds for a window of years to files with stable names
+/// (`calendar.ics`, `calendar_FEED.ics`), returning how many events are new or changed
+///
+/// `years` lists the years of the window that are available, in ascending order, and
+/// `data` holds their days; seasons and periods are only emitted for those years.
+pub fn generate_rolling_ical(years: Vec<i32>, data: &CalendarDays, options: &IcalOptions) -> Result<usize> {
+    write_feeds(&Span::Rolling(years), data, options)
+}
+
+fn write_feeds(span: &Span, data: &CalendarDays, options: &IcalOptions) -> Result<usize> {
+    let mut changed = 0;
+    for &feed in &options.feeds {
+        changed += write_feed(span, data, options, feed)?;
+    }
+    Ok(changed)
+}
+
+fn write_feed(span: &Span, data: &CalendarDays, options: &IcalOptions, feed: Feed) -> Result<usize> {
+    let path = get_ical_path(span, feed)?;
+    let previous = if path.exists() {
+        fs::read_to_string(&path)?
+    } else {
+        String::new()
+    };
+    let (ics, changed) = render_feed(span, data, options, feed, &previous);
+
+    write_atomic(&path, ics.as_bytes())
         .with_context(|| format!("Failed to write iCal file: {:?}", path))?;
-    Ok(())
-} 
\ No newline at end of file
+
+    if history::enabled() {
+        let file = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let message = format!("{}: {} event(s) changed", file, changed);
+        history::Repo::ical()?.commit_file(&file, &message)?;
+    }
+    Ok(changed)
+}
+
+// Name of every calendar unless the config file sets one
+const DEFAULT_CALENDAR_NAME: &str = "Orthodox Calendar";
+
+// How often subscribed clients check for updates unless the config file says otherwise
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::days(1);
+
+/// Builds an empty calendar with the properties subscribing clients read
+///
+/// `icalendar` does not escape NAME and the X-WR- properties, so they are escaped here.
+fn calendar_header(span: &Span, feed: Feed, subscription: &Subscription) -> Calendar {
+    let mut title = subscription.name.as_deref().unwrap_or(DEFAULT_CALENDAR_NAME).to_string();
+    if let Span::Year(year) = span {
+        title = format!("{} {}", title, year);
+    }
+    let name = match feed {
+        Feed::All => title,
+        _ => format!("{}: {}", title, feed.category()),
+    };
+    let description = subscription.description.as_deref().unwrap_or(feed.description());
+    let prodid = subscription.prodid.clone()
+        .unwrap_or_else(|| format!("-//orthoterm//orthoterm {}//EN", env!("CARGO_PKG_VERSION")));
+    let refresh_interval = format_duration(subscription.refresh_interval.map_or(DEFAULT_REFRESH_INTERVAL, |interval| interval.0));
+
+    let mut calendar = Calendar::empty();
+    calendar.append_property(Property::new("VERSION", "2.0"));
+    calendar.append_property(Property::new("PRODID", &prodid));
+    calendar.append_property(Property::new("CALSCALE", "GREGORIAN"));
+    calendar.append_property(Property::new("NAME", escape_text(&name)));
+    calendar.append_property(Property::new("X-WR-CALNAME", escape_text(&name)));
+    calendar.append_property(Property::new("DESCRIPTION", description));
+    calendar.append_property(Property::new("X-WR-CALDESC", escape_text(description)));
+    if let Some(timezone) = &subscription.timezone {
+        calendar.append_property(Property::new("X-WR-TIMEZONE", escape_text(timezone)));
+    }
+    calendar.append_property(
+        Property::new("REFRESH-INTERVAL", &refresh_interval)
+            .add_parameter("VALUE", "DURATION")
+            .done(),
+    );
+    calendar.append_property(Property::new("X-PUBLISHED-TTL", &refresh_interval));
+    if let Some(color) = &subscription.color {
+        calendar.append_property(Property::new("COLOR", color));
+    }
+    if let Some(url) = &subscription.url {
+        let source = format!("{}/{}", url.trim_end_matches('/'), feed.file_name(span));
+        calendar.append_property(Property::new("SOURCE", &source).add_parameter("VALUE", "URI").done());
+    }
+    calendar
+}
+
+/// Returns the source page of a scraped day, for the URL of its events
+fn day_url(date: NaiveDate, day_data: &OrthoCalendarData) -> Option<String> {
+    (day_data.origin == DataOrigin::Scraped && SCRAPED_YEARS.contains(&date.year()))
+        .then(|| source_url(date.month(), date.day(), date.year()))
+}
+
+/// Serializes a feed, returning it with how many events differ from the previous file's
+fn render_feed(span: &Span, data: &CalendarDays, options: &IcalOptions, feed: Feed, previous: &str) -> (String, usize) {
+    let events = feed_events(span, data, options, feed);
+
+    let mut calendar = calendar_header(span, feed, &options.subscription);
+
+    // Carry over the previous file's sequence numbers, bumping those of changed events
+    let previous = previous_events(previous);
+    let mut changed = 0;
+    for mut event in events {
+        let (uid, _, content) = event_content(&event.to_string());
+        let sequence = match previous.get(&uid) {
+            Some((sequence, old_content)) if *old_content == content => *sequence,
+            Some((sequence, _)) => {
+                changed += 1;
+                sequence + 1
+            }
+            None => {
+                changed += 1;
+                0
+            }
+        };
+        if sequence > 0 {
+            event.sequence(sequence);
+        }
+        calendar.push(event);
+    }
+
+    // `icalendar` can fold lines past 75 octets when they hold multi-byte characters
+    (refold(&calendar.to_string()), changed)
+}
+
+/// Splits a rendered VEVENT into its UID, SEQUENCE and the rest of its content lines
+///
+/// DTSTAMP and LAST-MODIFIED are left out of the content, so only changes clients
+/// care about count as a change.
+fn event_content(vevent: &str) -> (String, u32, Vec<String>) {
+    let mut uid = String::new();
+    let mut sequence = 0;
+    let mut content: Vec<String> = Vec::new();
+
+    // Unfold continuation lines first
+    for line in vevent.split("\r\n").filter(|line| !line.is_empty()) {
+        match (line.strip_prefix(' '), content.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => content.push(line.to_string()),
+        }
+    }
+
+    content.retain(|line| {
+        let name = line.split([':', ';']).next().unwrap_or_default();
+        match name {
+            // Alarms carry UIDs of their own after the event's
+            "UID" if uid.is_empty() => uid = line["UID:".len()..].to_string(),
+            "SEQUENCE" => sequence = line["SEQUENCE:".len()..].parse().unwrap_or(0),
+            "DTSTAMP" | "LAST-MODIFIED" => {}
+            _ => return true,
+        }
+        false
+    });
+    (uid, sequence, content)
+}
+
+/// Reads the events of a previously generated file, keyed by UID
+fn previous_events(ics: &str) -> HashMap<String, (u32, Vec<String>)> {
+    ics.split("BEGIN:VEVENT")
+        .skip(1)
+        .filter_map(|part| part.split("END:VEVENT").next())
+        .map(|vevent| {
+            let (uid, sequence, content) = event_content(&format!("BEGIN:VEVENT{}END:VEVENT\r\n", vevent));
+            (uid, (sequence, content))
+        })
+        .collect()
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use crate::computed::{compute_day, compute_year_data};
+    use crate::rfc5545::validate;
+    use ical::parser::ical::component::{IcalCalendar, IcalEvent};
+    use std::io::BufReader;
+
+    const YEAR: i32 = 1850;
+
+    // Scraped text with everything that needs escaping or careful folding
+    const SUMMARY: &str = "St. Nicholas, Archbishop of Myra; Wonderworker";
+    const LIVES: [&str; 3] = [
+        r"Saint Nicholas \ the Wonderworker (see C:\lives\nicholas)",
+        "Святитель Николай, архиепископ Мир Ликийских, чудотворец; память 6 декабря",
+        "Ἅγιος Νικόλαος ὁ Θαυματουργός, ἀρχιεπίσκοπος Μύρων τῆς Λυκίας ☦",
+    ];
+
+    fn sample_year() -> CalendarDays {
+        let mut data = compute_year_data(YEAR).unwrap();
+        let day = OrthoCalendarData::new(
+            "December 18, 1850".to_string(),
+            "December 6, 1850".to_string(),
+            SUMMARY.to_string(),
+            "Fish allowed.\n\nVigil the evening before.".to_string(),
+            LIVES.iter().map(|life| life.to_string()).collect(),
+            vec!["Troparion, Tone 4: The truth of things revealed thee".to_string()],
+            vec!["Heb. 13:17-21".to_string(), "Luke 6:17-23".to_string()],
+        ).unwrap();
+        data.insert(day.gregorian_date().unwrap(), day);
+        data
+    }
+
+    fn all_options() -> IcalOptions {
+        IcalOptions {
+            festal_periods: true,
+            observances: true,
+            fasting_seasons: true,
+            fasting_days: true,
+            feeds: Feed::ALL.to_vec(),
+            ..IcalOptions::default()
+        }
+    }
+
+    /// Parses with the `ical` crate, which shares no code with `icalendar`
+    fn parse(ics: &str) -> IcalCalendar {
+        let mut parser = ical::IcalParser::new(BufReader::new(ics.as_bytes()));
+        let calendar = parser.next().expect("no calendar in the output").expect("output does not parse");
+        assert!(parser.next().is_none(), "more than one calendar in the output");
+        calendar
+    }
+
+    fn property<'a>(event: &'a IcalEvent, name: &str) -> Option<&'a str> {
+        event.properties.iter()
+            .find(|property| property.name == name)
+            .and_then(|property| property.value.as_deref())
+    }
+
+    /// Reverses RFC 5545 TEXT escaping, which the `ical` crate leaves in place
+    fn unescape(value: &str) -> String {
+        let mut text = String::new();
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                text.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n' | 'N') => text.push('\n'),
+                Some(escaped) => text.push(escaped),
+                None => {}
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn every_feed_is_valid_icalendar() {
+        let data = sample_year();
+        let options = all_options();
+        for feed in Feed::ALL {
+            let (ics, _) = render_feed(&Span::Year(YEAR), &data, &options, feed, "");
+            assert_eq!(validate(&ics), Vec::<String>::new(), "{} feed", feed.name());
+
+            let calendar = parse(&ics);
+            assert_eq!(calendar.events.len(), feed_events(&Span::Year(YEAR), &data, &options, feed).len(), "{} feed", feed.name());
+            let uids: HashSet<&str> = calendar.events.iter().filter_map(|event| property(event, "UID")).collect();
+            assert_eq!(uids.len(), calendar.events.len(), "duplicate UIDs in the {} feed", feed.name());
+        }
+    }
+
+    #[test]
+    fn text_survives_escaping_and_folding() {
+        let (ics, _) = render_feed(&Span::Year(YEAR), &sample_year(), &IcalOptions::default(), Feed::All, "");
+        let calendar = parse(&ics);
+        let event = calendar.events.iter()
+            .find(|event| property(event, "UID") == Some("18501218-day@orthoterm"))
+            .expect("no event for the sample day");
+
+        assert_eq!(unescape(property(event, "SUMMARY").unwrap()), SUMMARY);
+        let description = unescape(property(event, "DESCRIPTION").unwrap());
+        assert!(description.contains(&format!("Saints:\n{}", LIVES.join("\n"))), "{}", description);
+        assert!(description.contains("Fish allowed.\n\nVigil the evening before."), "{}", description);
+
+        let alt = event.properties.iter().find(|property| property.name == "X-ALT-DESC").expect("no X-ALT-DESC");
+        assert_eq!(alt.params, Some(vec![("FMTTYPE".to_string(), vec!["text/html".to_string()])]));
+        let html = unescape(alt.value.as_deref().unwrap());
+        assert!(html.contains("<p>Fish allowed.</p><p>Vigil the evening before.</p>"), "{}", html);
+        assert!(html.contains(&format!("<li>{}</li>", LIVES[1])), "{}", html);
+        assert!(html.contains("<p><i>Troparion, Tone 4</i><br>The truth of things revealed thee</p>"), "{}", html);
+        assert!(html.contains(">Luke 6:17-23</a>"), "{}", html);
+    }
+
+    #[test]
+    fn custom_description_replaces_the_html_layout() {
+        let options = IcalOptions {
+            description_template: Template::parse("Commemorations <{julian_date}>:\n{lives}\n\nRead {scripture}").unwrap(),
+            custom_description: true,
+            ..IcalOptions::default()
+        };
+        let (ics, _) = render_feed(&Span::Year(YEAR), &sample_year(), &options, Feed::All, "");
+        assert_eq!(validate(&ics), Vec::<String>::new());
+        let calendar = parse(&ics);
+        let event = calendar.events.iter()
+            .find(|event| property(event, "UID") == Some("18501218-day@orthoterm"))
+            .expect("no event for the sample day");
+
+        let alt = event.properties.iter().find(|property| property.name == "X-ALT-DESC").expect("no X-ALT-DESC");
+        let html = unescape(alt.value.as_deref().unwrap());
+        assert!(html.starts_with("<html><body><p>Commemorations &lt;December 6, 1850&gt;:<br>"), "{}", html);
+        assert!(html.contains(&format!("<br>{}</p><p>Read Heb. 13:17-21<br>Luke 6:17-23</p>", LIVES[2])), "{}", html);
+        assert!(!html.contains("<h3>"), "{}", html);
+    }
+
+    #[test]
+    fn subscription_properties_are_escaped() {
+        let options = IcalOptions {
+            subscription: Subscription {
+                name: Some("St. Nicholas, Parish".to_string()),
+                description: Some("Feasts; fasts".to_string()),
+                url: Some("https://example.org/calendar/".to_string()),
+                ..Subscription::default()
+            },
+            ..IcalOptions::default()
+        };
+        let (ics, _) = render_feed(&Span::Year(YEAR), &sample_year(), &options, Feed::Feasts, "");
+        assert_eq!(validate(&ics), Vec::<String>::new());
+
+        let calendar = parse(&ics);
+        let value = |name: &str| calendar.properties.iter()
+            .find(|property| property.name == name)
+            .and_then(|property| property.value.clone())
+            .map(|value| unescape(&value));
+        assert_eq!(value("X-WR-CALNAME").as_deref(), Some("St. Nicholas, Parish 1850: Great Feasts"));
+        assert_eq!(value("X-WR-CALDESC").as_deref(), Some("Feasts; fasts"));
+        assert_eq!(value("REFRESH-INTERVAL").as_deref(), Some("P1D"));
+        assert_eq!(value("SOURCE").as_deref(), Some("https://example.org/calendar/calendar_1850_feasts.ics"));
+    }
+
+    #[test]
+    fn rolling_feed_spans_years_without_duplicates() {
+        let mut data = sample_year();
+        data.extend(compute_year_data(YEAR + 1).unwrap());
+        let span = Span::Rolling(vec![YEAR, YEAR + 1]);
+        assert_eq!(Feed::Fasting.file_name(&span), "calendar_fasting.ics");
+
+        let (ics, _) = render_feed(&span, &data, &all_options(), Feed::All, "");
+        assert_eq!(validate(&ics), Vec::<String>::new());
+        let calendar = parse(&ics);
+        let uids: HashSet<&str> = calendar.events.iter().filter_map(|event| property(event, "UID")).collect();
+        assert_eq!(uids.len(), calendar.events.len());
+        assert!(uids.iter().any(|uid| uid.starts_with("1850")) && uids.iter().any(|uid| uid.starts_with("1851")));
+        // The Nativity Fast beginning in 1850 ends in 1851, and is listed once
+        assert!(uids.contains("18501127-fasting-season-nativity-fast@orthoterm"));
+    }
+
+    #[test]
+    fn rolling_feed_leaves_out_missing_years() {
+        let mut data = sample_year();
+        data.extend(compute_year_data(YEAR + 2).unwrap());
+        let span = Span::Rolling(vec![YEAR, YEAR + 2]);
+
+        let events = feed_events(&span, &data, &all_options(), Feed::All);
+        let uids: HashSet<String> = events.iter()
+            .filter_map(|event| event.property_value("UID").map(str::to_string))
+            .collect();
+        assert_eq!(uids.len(), events.len());
+        // Only the Nativity Fast running from 1851 into January 1852 starts in the missing year
+        let missing: Vec<&str> = uids.iter().map(String::as_str).filter(|uid| uid.starts_with("1851")).collect();
+        assert_eq!(missing, ["18511127-fasting-season-nativity-fast@orthoterm"]);
+        assert!(uids.contains("18501127-fasting-season-nativity-fast@orthoterm"));
+        assert!(uids.contains("18521127-fasting-season-nativity-fast@orthoterm"));
+    }
+
+    #[test]
+    fn fast_free_days_are_not_fast_days() {
+        let options = IcalOptions::default();
+        for (month, day) in [(5, 6), (5, 8)] {
+            // Bright Monday and Bright Wednesday
+            let date = NaiveDate::from_ymd_opt(YEAR, month, day).unwrap();
+            let day_data = compute_day(date).unwrap();
+            assert_eq!(day_data.fasting, Some(FastingLevel::FastFree));
+            assert!(!Feed::Fasting.has_content(date, &day_data));
+            assert!(feed_day_event(Feed::Fasting, date, &day_data, &options).is_none());
+        }
+
+        // A Wednesday after Thomas Sunday is a fast day again
+        let date = NaiveDate::from_ymd_opt(YEAR, 5, 15).unwrap();
+        let day_data = compute_day(date).unwrap();
+        assert!(Feed::Fasting.has_content(date, &day_data));
+        assert!(feed_day_event(Feed::Fasting, date, &day_data, &options).is_some());
+    }
+
+    #[test]
+    fn regenerating_is_stable() {
+        let data = sample_year();
+        let (first, changed) = render_feed(&Span::Year(YEAR), &data, &IcalOptions::default(), Feed::All, "");
+        assert!(changed > 0);
+        let (second, changed) = render_feed(&Span::Year(YEAR), &data, &IcalOptions::default(), Feed::All, &first);
+        assert_eq!(changed, 0);
+        assert_eq!(first, second);
+    }
+}
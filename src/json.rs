@@ -1,11 +1,62 @@
 use std::fs;
-use std::path::PathBuf;
-use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use serde_json;
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use regex::Regex;
-use dirs;
+use crate::liturgy::{liturgy_for, Liturgy};
+use crate::feasts::{festal_days, FestalDay};
+use crate::coincidences::{coincidences_on, Coincidence};
+use crate::fasting::{fasting_for, FastingLevel};
+use crate::paschalion::tone;
+use crate::observances::{observances_on, Observance};
+use crate::store::CalendarStore;
+use crate::fsutil::write_atomic;
+use crate::config;
+use crate::history;
+use crate::calendar::PARSER_VERSION;
+use crate::schema::{parse_calendar, serialize_calendar, SCHEMA_VERSION};
+
+/// Whether a day was fetched from the source website at all
+///
+/// Which of its fields were computed is recorded per field, see `OrthoCalendarData::computed`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataOrigin {
+    #[default]
+    Scraped,
+    Computed,
+}
+
+/// Fields that `annotate` always computes from the date, for scraped days too
+pub const ANNOTATED_FIELDS: [&str; 6] = ["liturgy", "festal", "coincidences", "fasting", "tone", "observances"];
+
+/// Fields that are scraped from the source website, or computed for years it does not cover
+pub const TEXT_FIELDS: [&str; 6] = ["julian_date", "summary", "liturgical_notes", "lives", "troparia", "scripture"];
+
+/// Where and when a scraped day was fetched, and how it was parsed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub fetched_at: DateTime<Utc>,
+    pub source: String,         // Source page for the date, see `calendar::source_url`
+    pub parser_version: u32,    // `calendar::PARSER_VERSION` at the time the day was parsed
+    pub content_sha256: String, // Hex digest of the raw HTML of all sections, in fetch order
+}
+
+impl Provenance {
+    /// Returns true if the day was parsed by an older parser and fetched before `date`
+    ///
+    /// Days parsed by the current parser are never stale, however old they are.
+    pub fn is_stale(&self, date: NaiveDate) -> bool {
+        self.parser_version < PARSER_VERSION && self.fetched_at.date_naive() < date
+    }
+}
+
+/// A calendar's days keyed by their Gregorian date
+///
+/// Inserting a day replaces any existing entry for the same date.
+pub type CalendarDays = BTreeMap<NaiveDate, OrthoCalendarData>;
 
 /// Represents a single day's worth of Orthodox calendar data
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,6 +68,24 @@ pub struct OrthoCalendarData {
     pub lives: Vec<String>,
     pub troparia: Vec<String>,
     pub scripture: Vec<String>,
+    #[serde(default)]
+    pub liturgy: Option<Liturgy>, // Computed from the date, see `annotate`
+    #[serde(default)]
+    pub festal: Vec<FestalDay>,   // Forefeast/feast/afterfeast/apodosis of a Great Feast
+    #[serde(default)]
+    pub coincidences: Vec<Coincidence>, // Concurrences of a Great Feast with the Paschal cycle
+    #[serde(default)]
+    pub fasting: Option<FastingLevel>,
+    #[serde(default)]
+    pub tone: Option<u8>,         // Tone of the week, none in Holy and Bright Weeks
+    #[serde(default)]
+    pub observances: Vec<Observance>, // Soul Saturdays and other computed observances
+    #[serde(default)]
+    pub origin: DataOrigin,
+    #[serde(default)]
+    pub computed: BTreeSet<String>, // Names of the fields computed rather than scraped
+    #[serde(default)]
+    pub provenance: Option<Provenance>, // Missing for computed days and days stored before it was recorded
 }
 
 impl OrthoCalendarData {
@@ -33,7 +102,7 @@ impl OrthoCalendarData {
         let parsed_date = parse_date(&gregorian_date)?;
         let date = parsed_date.format("%Y-%m-%d").to_string();
         
-        Ok(Self {
+        let mut data = Self {
             date,
             julian_date,
             summary,
@@ -41,7 +110,55 @@ impl OrthoCalendarData {
             lives,
             troparia,
             scripture,
-        })
+            liturgy: None,
+            festal: Vec::new(),
+            coincidences: Vec::new(),
+            fasting: None,
+            tone: None,
+            observances: Vec::new(),
+            origin: DataOrigin::Scraped,
+            computed: BTreeSet::new(),
+            provenance: None,
+        };
+        data.annotate();
+        Ok(data)
+    }
+
+    /// Returns the Gregorian date this day is stored under
+    pub fn gregorian_date(&self) -> Result<NaiveDate> {
+        NaiveDate::parse_from_str(&self.date, "%Y-%m-%d")
+            .with_context(|| format!("Malformed date: {:?}", self.date))
+    }
+
+    /// Returns true if the named field was computed rather than scraped
+    pub fn is_computed(&self, field: &str) -> bool {
+        self.computed.contains(field)
+    }
+
+    /// Marks the named fields as computed rather than scraped
+    pub fn mark_computed(&mut self, fields: &[&str]) {
+        self.computed.extend(fields.iter().map(|field| field.to_string()));
+    }
+
+    /// Fills in the fields that are computed from the date rather than scraped
+    ///
+    /// Days with a malformed date are left as they are; `orthoterm check` reports them.
+    pub fn annotate(&mut self) {
+        // Computed days stored before fields were marked individually
+        if self.origin == DataOrigin::Computed && self.computed.is_empty() {
+            self.mark_computed(&TEXT_FIELDS);
+        }
+
+        let Ok(date) = self.gregorian_date() else {
+            return;
+        };
+        self.mark_computed(&ANNOTATED_FIELDS);
+        self.liturgy = Some(liturgy_for(date));
+        self.festal = festal_days(date);
+        self.coincidences = coincidences_on(date);
+        self.fasting = Some(fasting_for(date));
+        self.tone = tone(date);
+        self.observances = observances_on(date);
     }
 }
 
@@ -120,38 +237,138 @@ pub fn create_calendar_data(
     )
 }
 
-pub fn get_data_dir() -> PathBuf {
-    let mut path = dirs::data_local_dir().unwrap_or_else(|| PathBuf::from("."));
-    path.push("orthoterm");
-    path.push("data");
-    fs::create_dir_all(&path).unwrap_or_default();
-    path
+pub fn get_data_dir() -> Result<PathBuf> {
+    config::data_dir()
 }
 
-pub fn get_calendar_path(year: i32) -> PathBuf {
-    let mut path = get_data_dir();
+pub fn get_calendar_path(year: i32) -> Result<PathBuf> {
+    let mut path = get_data_dir()?;
     path.push(format!("calendar_{}.json", year));
-    path
+    Ok(path)
+}
+
+pub fn calendar_exists(year: i32) -> Result<bool> {
+    Ok(get_calendar_path(year)?.exists())
 }
 
-pub fn calendar_exists(year: i32) -> bool {
-    get_calendar_path(year).exists()
+/// Keys stored entries by date; later entries replace earlier ones and malformed dates are skipped
+pub fn index_by_date(entries: Vec<OrthoCalendarData>) -> CalendarDays {
+    entries.into_iter()
+        .filter_map(|day| Some((day.gregorian_date().ok()?, day)))
+        .collect()
 }
 
+/// Loads a year's entries exactly as stored, including any duplicates
 pub fn load_calendar(year: i32) -> Result<Vec<OrthoCalendarData>> {
-    let path = get_calendar_path(year);
-    let contents = fs::read_to_string(path)?;
-    let calendar: Vec<OrthoCalendarData> = serde_json::from_str(&contents)?;
+    let path = get_calendar_path(year)?;
+    let contents = fs::read_to_string(&path)?;
+    let (mut calendar, version) = parse_calendar(&contents)
+        .with_context(|| format!("Failed to load calendar file: {:?}", path))?;
+    for day in &mut calendar {
+        day.annotate();
+    }
+
+    // Persist the upgrade so older files are only migrated once; every entry is
+    // kept, duplicates included, so `orthoterm check` still sees them
+    if version < SCHEMA_VERSION {
+        println!("Upgrading {:?} from schema version {} to {}", path, version, SCHEMA_VERSION);
+        write_calendar_file(&path, &serialize_calendar(&calendar)?, |file| {
+            format!("{}: upgraded from schema version {} to {}", file, version, SCHEMA_VERSION)
+        })?;
+    }
     Ok(calendar)
 }
 
-pub fn save_yearly_calendar(year: i32, data: &[OrthoCalendarData]) -> Result<()> {
-    let path = get_calendar_path(year);
-    let json = serde_json::to_string_pretty(data)?;
-    fs::write(path, json)?;
+pub fn save_yearly_calendar(year: i32, data: &CalendarDays) -> Result<()> {
+    write_yearly_calendar(year, data, None)
+}
+
+/// Replaces a year with the version stored at a past revision of the history
+pub fn restore_yearly_calendar(year: i32, revision: &str) -> Result<usize> {
+    let path = get_calendar_path(year)?;
+    let file = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let contents = history::Repo::data()?.file_at(revision, &file)?;
+    let (mut calendar, _) = parse_calendar(&contents)
+        .with_context(|| format!("Failed to parse {} at {}", file, revision))?;
+    for day in &mut calendar {
+        day.annotate();
+    }
+
+    let data = index_by_date(calendar);
+    write_yearly_calendar(year, &data, Some(&format!("restored from {}", revision)))?;
+    Ok(data.len())
+}
+
+fn write_yearly_calendar(year: i32, data: &CalendarDays, action: Option<&str>) -> Result<()> {
+    let path = get_calendar_path(year)?;
+
+    // The previous version is only needed to describe the change in the history
+    let previous = if history::enabled() && path.exists() {
+        let (mut calendar, _) = parse_calendar(&fs::read_to_string(&path)?)?;
+        for day in &mut calendar {
+            day.annotate();
+        }
+        index_by_date(calendar)
+    } else {
+        CalendarDays::new()
+    };
+
+    write_calendar_file(&path, &serialize_calendar(data.values())?, |file| {
+        history::summarize_calendar_change(file, action, &previous, data)
+    })
+}
+
+/// Writes a calendar file and, with history enabled, commits it with the message
+/// built from the file's name
+fn write_calendar_file(path: &Path, json: &str, message: impl FnOnce(&str) -> String) -> Result<()> {
+    write_atomic(path, json.as_bytes())?;
+
+    if history::enabled() {
+        let file = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        history::Repo::data()?.commit_file(&file, &message(&file))?;
+    }
     Ok(())
 }
 
+/// Stores each year as a pretty-printed `calendar_YEAR.json` in the data directory
+pub struct JsonStore;
+
+impl CalendarStore for JsonStore {
+    fn exists(&self, year: i32) -> Result<bool> {
+        calendar_exists(year)
+    }
+
+    fn load_entries(&self, year: i32) -> Result<Vec<OrthoCalendarData>> {
+        load_calendar(year)
+    }
+
+    fn save(&self, year: i32, data: &CalendarDays) -> Result<()> {
+        save_yearly_calendar(year, data)
+    }
+
+    fn years(&self) -> Result<Vec<i32>> {
+        let mut years: Vec<i32> = fs::read_dir(get_data_dir()?)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                entry.file_name().to_str()?
+                    .strip_prefix("calendar_")?
+                    .strip_suffix(".json")?
+                    .parse().ok()
+            })
+            .collect();
+        years.sort();
+        Ok(years)
+    }
+
+    fn saved_at(&self, year: i32) -> Result<Option<DateTime<Utc>>> {
+        let path = get_calendar_path(year)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(fs::metadata(&path)?.modified()?.into()))
+    }
+}
+
 pub fn parse_date(date_str: &str) -> Result<NaiveDate> {
     // Try parsing with non-padded day format
     if let Ok(date) = NaiveDate::parse_from_str(date_str, "%B %-d, %Y") {
@@ -160,3 +377,26 @@ pub fn parse_date(date_str: &str) -> Result<NaiveDate> {
 
     Err(anyhow::anyhow!("Could not parse date: {}", date_str))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provenance(fetched_at: &str, parser_version: u32) -> Provenance {
+        Provenance {
+            fetched_at: DateTime::parse_from_rfc3339(fetched_at).unwrap().with_timezone(&Utc),
+            source: "https://www.holytrinityorthodox.com/calendar/".to_string(),
+            parser_version,
+            content_sha256: String::new(),
+        }
+    }
+
+    #[test]
+    fn staleness_needs_an_older_parser() {
+        let before = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        assert!(!provenance("2020-01-01T00:00:00Z", PARSER_VERSION).is_stale(before));
+        assert!(!provenance("2025-07-01T00:00:00Z", PARSER_VERSION).is_stale(before));
+        assert!(provenance("2020-01-01T00:00:00Z", PARSER_VERSION - 1).is_stale(before));
+        assert!(!provenance("2025-07-01T00:00:00Z", PARSER_VERSION - 1).is_stale(before));
+    }
+}
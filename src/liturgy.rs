@@ -0,0 +1,145 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::paschalion::{days_from_pascha, gregorian_to_julian};
+
+/// The Divine Liturgy (if any) served on a given day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Liturgy {
+    Chrysostom,
+    Basil,
+    Presanctified,
+    Aliturgical,
+}
+
+impl Liturgy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Liturgy::Chrysostom => "Liturgy of St. John Chrysostom",
+            Liturgy::Basil => "Liturgy of St. Basil the Great",
+            Liturgy::Presanctified => "Liturgy of the Presanctified Gifts",
+            Liturgy::Aliturgical => "No Liturgy (aliturgical day)",
+        }
+    }
+}
+
+impl fmt::Display for Liturgy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+fn is_weekend(weekday: Weekday) -> bool {
+    matches!(weekday, Weekday::Sat | Weekday::Sun)
+}
+
+/// Determines which Liturgy is served on the given (Gregorian) date
+///
+/// Fixed feasts are reckoned on the Julian calendar, as in the source data.
+pub fn liturgy_for(date: NaiveDate) -> Liturgy {
+    let offset = days_from_pascha(date);
+    let weekday = date.weekday();
+    let julian = gregorian_to_julian(date);
+    let annunciation = julian.is(3, 25);
+
+    // Holy Week: St. Basil on Great Thursday and Great Saturday, and Great Friday
+    // is aliturgical unless the Annunciation falls on it
+    match offset {
+        -3 | -1 => return Liturgy::Basil,
+        -2 if annunciation => return Liturgy::Chrysostom,
+        -2 => return Liturgy::Aliturgical,
+        _ => {}
+    }
+
+    // Great Lent (Clean Monday to the Friday before Lazarus Saturday) and Holy Monday
+    // to Wednesday; Lazarus Saturday itself has the usual Liturgy of St. John Chrysostom
+    let great_lent = (-48..=-9).contains(&offset);
+    let holy_week = (-6..=-4).contains(&offset);
+    if great_lent || holy_week {
+        return match weekday {
+            Weekday::Sun => Liturgy::Basil,
+            Weekday::Sat => Liturgy::Chrysostom,
+            _ if annunciation => Liturgy::Chrysostom,
+            Weekday::Wed | Weekday::Fri => Liturgy::Presanctified,
+            _ if holy_week => Liturgy::Presanctified,
+            _ => Liturgy::Aliturgical,
+        };
+    }
+
+    // Wednesday and Friday of Cheesefare week, unless the Meeting of the Lord falls on them
+    if matches!(offset, -53 | -51) && !julian.is(2, 2) {
+        return Liturgy::Aliturgical;
+    }
+
+    // Eves of the Nativity and Theophany: St. Basil is served on the eve, unless
+    // the eve falls on a weekend, in which case it moves to the feast itself and
+    // the Royal Hours are read on the preceding (aliturgical) Friday
+    if julian.is(12, 24) || julian.is(1, 5) {
+        return if is_weekend(weekday) { Liturgy::Chrysostom } else { Liturgy::Basil };
+    }
+    if julian.is(12, 25) || julian.is(1, 6) {
+        let eve = date.pred_opt().map(|d| d.weekday()).unwrap_or(weekday);
+        return if is_weekend(eve) { Liturgy::Basil } else { Liturgy::Chrysostom };
+    }
+    if weekday == Weekday::Fri && (julian.is(12, 22) || julian.is(12, 23) || julian.is(1, 3) || julian.is(1, 4)) {
+        return Liturgy::Aliturgical;
+    }
+
+    // Circumcision of the Lord and St. Basil the Great
+    if julian.is(1, 1) {
+        return Liturgy::Basil;
+    }
+
+    Liturgy::Chrysostom
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn great_lent() {
+        // Pascha 2025 is April 20
+        assert_eq!(liturgy_for(date(2025, 3, 3)), Liturgy::Aliturgical); // Clean Monday
+        assert_eq!(liturgy_for(date(2025, 3, 5)), Liturgy::Presanctified);
+        assert_eq!(liturgy_for(date(2025, 3, 9)), Liturgy::Basil);
+        assert_eq!(liturgy_for(date(2025, 4, 11)), Liturgy::Presanctified);
+        assert_eq!(liturgy_for(date(2025, 4, 12)), Liturgy::Chrysostom); // Lazarus Saturday
+        assert_eq!(liturgy_for(date(2025, 4, 14)), Liturgy::Presanctified); // Holy Monday
+    }
+
+    #[test]
+    fn holy_week() {
+        assert_eq!(liturgy_for(date(2025, 4, 17)), Liturgy::Basil);
+        assert_eq!(liturgy_for(date(2025, 4, 18)), Liturgy::Aliturgical);
+        assert_eq!(liturgy_for(date(2025, 4, 19)), Liturgy::Basil);
+    }
+
+    #[test]
+    fn annunciation_in_holy_week() {
+        // Annunciation (April 7) on Holy Monday in 2026 and on Great Friday in 2034
+        assert_eq!(liturgy_for(date(2026, 4, 7)), Liturgy::Chrysostom);
+        assert_eq!(liturgy_for(date(2034, 4, 7)), Liturgy::Chrysostom);
+    }
+
+    #[test]
+    fn nativity_and_theophany_eves() {
+        // Nativity Eve on a Monday in 2025: St. Basil on the eve
+        assert_eq!(liturgy_for(date(2025, 1, 6)), Liturgy::Basil);
+        assert_eq!(liturgy_for(date(2025, 1, 7)), Liturgy::Chrysostom);
+        // Nativity Eve on a Saturday in 2024: St. Basil moves to the feast, and the
+        // Royal Hours are read on the aliturgical Friday before
+        assert_eq!(liturgy_for(date(2024, 1, 5)), Liturgy::Aliturgical);
+        assert_eq!(liturgy_for(date(2024, 1, 6)), Liturgy::Chrysostom);
+        assert_eq!(liturgy_for(date(2024, 1, 7)), Liturgy::Basil);
+        // Theophany Eve on a Sunday in 2015
+        assert_eq!(liturgy_for(date(2015, 1, 16)), Liturgy::Aliturgical);
+        assert_eq!(liturgy_for(date(2015, 1, 18)), Liturgy::Chrysostom);
+        assert_eq!(liturgy_for(date(2015, 1, 19)), Liturgy::Basil);
+    }
+}
@@ -2,20 +2,81 @@ mod calendar;
 mod scraper;
 mod json;
 mod ical;
+mod paschalion;
+mod liturgy;
+mod feasts;
+mod coincidences;
+mod fasting;
+mod computed;
+mod observances;
+mod view;
+mod store;
+mod sqlite;
+mod schema;
+mod fsutil;
+mod config;
+mod check;
+mod bundle;
+mod diff;
+mod history;
+mod alarms;
+mod template;
+mod rfc5545;
+mod html;
 
-use chrono::{Local, Datelike, NaiveDate};
-use anyhow::Result;
+use chrono::{DateTime, Local, Datelike, NaiveDate, Utc};
+use sha2::{Digest, Sha256};
+use anyhow::{Context, Result};
 use std::env;
+use std::fs;
+use std::ops::RangeInclusive;
 use std::time::Duration;
-use crate::json::{OrthoCalendarData, save_yearly_calendar, calendar_exists, load_calendar, create_calendar_data, parse_date};
-use crate::ical::{generate_ical, ical_exists};
-use crate::calendar::fetch_calendar_content;
+use crate::json::{OrthoCalendarData, CalendarDays, Provenance, JsonStore, restore_yearly_calendar, create_calendar_data, get_data_dir};
+use crate::ical::{generate_ical, generate_rolling_ical, get_ical_dir, ical_up_to_date, Feed, IcalOptions};
+use crate::calendar::{cached_calendar_page, cached_page_fetched_at, fetch_calendar_page, parse_section, source_url, PARSER_VERSION, SCRAPED_YEARS};
+use crate::computed::{compute_day, compute_year_data};
+use crate::view::print_day;
+use crate::coincidences::coincidences;
+use crate::store::{CalendarStore, StoreKind, open_store, copy_years};
+use crate::sqlite::SqliteStore;
+use crate::fsutil::lock_dirs;
+use crate::config::Config;
+use std::path::PathBuf;
+use std::collections::BTreeSet;
+use crate::check::{check_year, Problem};
+use crate::bundle::{export_bundle, import_bundle};
+use crate::diff::diff_day;
+use crate::template::Template;
 
 const FETCH_DELAY: Duration = Duration::from_millis(100);
 
 fn fetch_day(year: i32, month: u32, day: u32) -> Result<OrthoCalendarData> {
+    build_day(year, month, day, Utc::now(), |section| fetch_calendar_page(month, day, year, section))
+}
+
+/// Rebuilds a day from the raw pages cached when it was last fetched
+fn reparse_day(year: i32, month: u32, day: u32) -> Result<OrthoCalendarData> {
+    let fetched_at = cached_page_fetched_at(month, day, year)?;
+    build_day(year, month, day, fetched_at, |section| cached_calendar_page(month, day, year, section))
+}
+
+fn build_day(
+    year: i32,
+    month: u32,
+    day: u32,
+    fetched_at: DateTime<Utc>,
+    fetch_page: impl Fn(&str) -> Result<String>,
+) -> Result<OrthoCalendarData> {
+    // Hash the raw pages as they are parsed, for the day's provenance
+    let mut hasher = Sha256::new();
+    let mut fetch_section = |section: &str| -> Result<Vec<String>> {
+        let html = fetch_page(section)?;
+        hasher.update(html.as_bytes());
+        parse_section(section, &html)
+    };
+
     // Fetch each section using the calendar module
-    let full_date = fetch_calendar_content(month, day, year, "dt")?
+    let full_date = fetch_section("dt")?
         .first()
         .ok_or_else(|| anyhow::anyhow!("No date found"))?
         .to_string();
@@ -34,44 +95,42 @@ fn fetch_day(year: i32, month: u32, day: u32) -> Result<OrthoCalendarData> {
         .trim()
         .to_string();
     
-    let header = fetch_calendar_content(month, day, year, "header")?
+    let header = fetch_section("header")?
         .first()
         .ok_or_else(|| anyhow::anyhow!("No header found"))?
         .to_string();
     
-    let lives_content = fetch_calendar_content(month, day, year, "lives")?;
-    let troparia_content = fetch_calendar_content(month, day, year, "trp")?;
-    let scripture_content = fetch_calendar_content(month, day, year, "scripture")?;
+    let lives_content = fetch_section("lives")?;
+    let troparia_content = fetch_section("trp")?;
+    let scripture_content = fetch_section("scripture")?;
     
     println!("Found gregorian date: {}", gregorian_date);
     println!("Found julian date: {}", julian_date);
     
-    create_calendar_data(
+    let mut data = create_calendar_data(
         gregorian_date,
         julian_date,
         header,           // Just pass the header as summary
         lives_content,
         troparia_content,
         scripture_content,
-    )
+    )?;
+    data.provenance = Some(Provenance {
+        fetched_at,
+        source: source_url(month, day, year),
+        parser_version: PARSER_VERSION,
+        content_sha256: hex::encode(hasher.finalize()),
+    });
+    Ok(data)
 }
 
-fn fetch_month_data(year: i32, month: u32) -> Result<Vec<OrthoCalendarData>> {
+fn fetch_month_data(year: i32, month: u32) -> Result<CalendarDays> {
     println!("Fetching calendar data for {}/{}", year, month);
     
-    let mut month_data = Vec::new();
-    let start_date = NaiveDate::from_ymd_opt(year, month, 1)
-        .ok_or_else(|| anyhow::anyhow!("Invalid start date"))?;
+    let mut month_data = CalendarDays::new();
+    let (start_date, last_day) = month_bounds(year, month)?;
     let mut current_date = start_date;
     
-    // Calculate last day of month
-    let last_day = if month == 12 {
-        NaiveDate::from_ymd_opt(year + 1, 1, 1)
-    } else {
-        NaiveDate::from_ymd_opt(year, month + 1, 1)
-    }.ok_or_else(|| anyhow::anyhow!("Invalid date"))?.pred_opt()
-        .ok_or_else(|| anyhow::anyhow!("Invalid date"))?;
-    
     while current_date <= last_day {
         println!("Fetching data for {}", current_date);
         
@@ -81,7 +140,7 @@ fn fetch_month_data(year: i32, month: u32) -> Result<Vec<OrthoCalendarData>> {
             current_date.day()
         )?;
         
-        month_data.push(calendar_data);
+        month_data.insert(current_date, calendar_data);
         current_date = current_date.succ_opt()
             .ok_or_else(|| anyhow::anyhow!("Invalid next date"))?;
         std::thread::sleep(FETCH_DELAY);
@@ -90,13 +149,21 @@ fn fetch_month_data(year: i32, month: u32) -> Result<Vec<OrthoCalendarData>> {
     Ok(month_data)
 }
 
-fn fetch_year_data(year: i32) -> Result<Vec<OrthoCalendarData>> {
-    let mut yearly_data = if calendar_exists(year) {
+fn fetch_year_data(store: &dyn CalendarStore, year: i32) -> Result<CalendarDays> {
+    // The source website only covers a limited range of years; compute the rest
+    if !SCRAPED_YEARS.contains(&year) {
+        println!("Year {} is outside the source's range, computing a reduced dataset", year);
+        let yearly_data = compute_year_data(year)?;
+        store.save(year, &yearly_data)?;
+        return Ok(yearly_data);
+    }
+
+    let mut yearly_data = if store.exists(year)? {
         println!("Found existing calendar data for year {}", year);
-        load_calendar(year)?
+        store.load(year)?
     } else {
         println!("Creating new calendar data for year {}", year);
-        Vec::new()
+        CalendarDays::new()
     };
 
     println!("Current data contains {} entries", yearly_data.len());
@@ -104,37 +171,20 @@ fn fetch_year_data(year: i32) -> Result<Vec<OrthoCalendarData>> {
     // Check which months we need to fetch
     for month in 1..=12 {
         let expected_days = days_in_month(year, month);
-        let month_entries = yearly_data.iter()
-            .filter(|data| {
-                parse_date(&data.date)
-                    .map(|date| date.month() == month)
-                    .unwrap_or(false)
-            })
-            .count();
+        let month_entries = month_entries(&yearly_data, year, month)?;
         
         println!("Month {}: have {} days, expect {} days", 
             month, month_entries, expected_days);
         
         if month_entries < expected_days as usize {
             println!("Fetching missing data for month {}", month);
-            let month_data = fetch_month_data(year, month)?;
-            
-            // Remove any partial data for this month
-            yearly_data.retain(|data| {
-                parse_date(&data.date)
-                    .map(|date| date.month() != month)
-                    .unwrap_or(true)
-            });
             
-            // Add new month data
-            yearly_data.extend(month_data);
-            
-            // Sort by date using the new date field
-            yearly_data.sort_by(|a, b| a.date.cmp(&b.date));
+            // Fetched days replace any partial data for this month
+            yearly_data.extend(fetch_month_data(year, month)?);
             
             // Save progress after each month
             println!("Saving progress: {} entries total", yearly_data.len());
-            save_yearly_calendar(year, &yearly_data)?;
+            store.save(year, &yearly_data)?;
         } else {
             println!("Month {} is complete", month);
         }
@@ -144,6 +194,19 @@ fn fetch_year_data(year: i32) -> Result<Vec<OrthoCalendarData>> {
     Ok(yearly_data)
 }
 
+// Helper function to get the first and last day of a month
+fn month_bounds(year: i32, month: u32) -> Result<(NaiveDate, NaiveDate)> {
+    let start_date = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| anyhow::anyhow!("Invalid start date"))?;
+    let last_day = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }.ok_or_else(|| anyhow::anyhow!("Invalid date"))?.pred_opt()
+        .ok_or_else(|| anyhow::anyhow!("Invalid date"))?;
+    Ok((start_date, last_day))
+}
+
 // Helper function to get days in month
 fn days_in_month(year: i32, month: u32) -> u32 {
     NaiveDate::from_ymd_opt(
@@ -156,26 +219,485 @@ fn days_in_month(year: i32, month: u32) -> u32 {
     .day()
 }
 
-fn is_month_complete(data: &[OrthoCalendarData], year: i32, month: u32) -> bool {
+fn month_entries(data: &CalendarDays, year: i32, month: u32) -> Result<usize> {
+    let (start_date, last_day) = month_bounds(year, month)?;
+    Ok(data.range(start_date..=last_day).count())
+}
+
+fn is_month_complete(data: &CalendarDays, year: i32, month: u32) -> bool {
     let expected_days = days_in_month(year, month) as usize;
-    let month_days = data.iter()
-        .filter(|entry| {
-            NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d")
-                .map(|date| date.month() == month)
-                .unwrap_or(false)
-        })
-        .count();
-    
-    month_days == expected_days
+    month_entries(data, year, month).is_ok_and(|month_days| month_days == expected_days)
 }
 
-fn is_year_complete(data: &[OrthoCalendarData], year: i32) -> bool {
+fn is_year_complete(data: &CalendarDays, year: i32) -> bool {
     (1..=12).all(|month| is_month_complete(data, year, month))
 }
 
+/// Prints a single day, using stored data when available and fetching it otherwise
+fn show_day(store: &dyn CalendarStore, date: NaiveDate) -> Result<()> {
+    let year = date.year();
+    let stored = if store.exists(year)? {
+        store.load(year)?
+    } else {
+        CalendarDays::new()
+    };
+
+    match stored.get(&date) {
+        Some(day_data) => print_day(day_data),
+        None if !SCRAPED_YEARS.contains(&year) => print_day(&compute_day(date)?),
+        None => print_day(&fetch_day(year, date.month(), date.day())?),
+    }
+}
+
+/// Parses a single year ("2025") or an inclusive range of years ("2025..2040")
+fn parse_year_range(arg: &str) -> Result<RangeInclusive<i32>> {
+    let invalid = || anyhow::anyhow!("Invalid year or range (expected YEAR or START..END): {}", arg);
+
+    let range = match arg.split_once("..") {
+        Some((start, end)) => {
+            let end = end.strip_prefix('=').unwrap_or(end);
+            start.parse().map_err(|_| invalid())?..=end.parse().map_err(|_| invalid())?
+        }
+        None => {
+            let year = arg.parse().map_err(|_| invalid())?;
+            year..=year
+        }
+    };
+
+    if range.is_empty() {
+        return Err(invalid());
+    }
+    Ok(range)
+}
+
+/// Lists the concurrences of fixed Great Feasts with the Paschal cycle
+fn list_coincidences(years: RangeInclusive<i32>) {
+    for year in years {
+        for (date, coincidence) in coincidences(year) {
+            println!("{}  {}", date, coincidence);
+        }
+    }
+}
+
+/// Validates the stored years, repairing them if asked to
+fn check_years(store: &dyn CalendarStore, years: Vec<i32>, repair: bool) -> Result<()> {
+    let mut found_problems = false;
+
+    for year in years {
+        if !store.exists(year)? {
+            println!("{}: no stored data", year);
+            continue;
+        }
+
+        let days = store.load_entries(year)?;
+        let problems = check_year(year, &days);
+        if problems.is_empty() {
+            println!("{}: OK ({} days)", year, days.len());
+            continue;
+        }
+
+        found_problems = true;
+        println!("{}: {} problem(s)", year, problems.len());
+        for problem in &problems {
+            println!("  {}", problem);
+        }
+        if repair {
+            repair_year(store, year, days, &problems)?;
+        }
+    }
+
+    if found_problems && !repair {
+        println!("Run `orthoterm check --repair` to fix these problems");
+    }
+    Ok(())
+}
+
+/// Refetches every day of the given years and prints how they differ from the
+/// stored days; with `apply`, stores the changed and missing days
+fn refresh_years(store: &dyn CalendarStore, years: RangeInclusive<i32>, apply: bool) -> Result<()> {
+    let mut found_changes = false;
+
+    for year in years {
+        if !SCRAPED_YEARS.contains(&year) {
+            println!("{}: not covered by the source, skipping", year);
+            continue;
+        }
+
+        let mut stored = if store.exists(year)? {
+            store.load(year)?
+        } else {
+            CalendarDays::new()
+        };
+
+        let mut changed = 0;
+        for month in 1..=12 {
+            let mut month_changed = 0;
+            for (date, fetched) in fetch_month_data(year, month)? {
+                let diff = diff_day(date, stored.get(&date), &fetched);
+                if diff.is_empty() {
+                    continue;
+                }
+                println!("{}", diff);
+                month_changed += 1;
+                if apply {
+                    stored.insert(date, fetched);
+                }
+            }
+
+            // Save each month as it is done, so a failed fetch later on loses nothing
+            if apply && month_changed > 0 {
+                store.save(year, &stored)?;
+            }
+            changed += month_changed;
+        }
+
+        println!("{}: {} day(s) differ from the source", year, changed);
+        if changed > 0 {
+            found_changes = true;
+            if apply {
+                println!("{}: changes applied", year);
+            }
+        }
+    }
+
+    if found_changes && !apply {
+        println!("Run `orthoterm refresh --apply` to store these changes");
+    }
+    Ok(())
+}
+
+/// Deduplicates and re-sorts a year, then re-parses or refetches only the bad days
+fn repair_year(store: &dyn CalendarStore, year: i32, days: Vec<OrthoCalendarData>, problems: &[Problem]) -> Result<()> {
+    // Keep the most complete copy of each day and drop entries from other years
+    let mut by_date = CalendarDays::new();
+    for day in days {
+        let Ok(date) = day.gregorian_date() else {
+            continue;
+        };
+        if date.year() != year {
+            continue;
+        }
+        let replace = by_date.get(&date)
+            .is_none_or(|existing| existing.summary.trim().is_empty() || !day.summary.trim().is_empty());
+        if replace {
+            by_date.insert(date, day);
+        }
+    }
+
+    let bad_days: BTreeSet<NaiveDate> = problems.iter().filter_map(Problem::bad_day).collect();
+    for date in bad_days {
+        let day = if !SCRAPED_YEARS.contains(&year) {
+            compute_day(date)?
+        } else {
+            match reparse_day(year, date.month(), date.day()) {
+                Ok(day) if !day.summary.trim().is_empty() => {
+                    println!("Re-parsed {} from cached pages", date);
+                    day
+                }
+                _ => {
+                    println!("Refetching {}", date);
+                    std::thread::sleep(FETCH_DELAY);
+                    fetch_day(year, date.month(), date.day())?
+                }
+            }
+        };
+        by_date.insert(date, day);
+    }
+
+    store.save(year, &by_date)?;
+    println!("Repaired {}: {} days saved", year, by_date.len());
+    Ok(())
+}
+
+/// Refetches the days of a year that were parsed by an older parser and fetched
+/// before `before`, returning how many were refetched
+///
+/// Days without provenance count as stale. Computed years are left alone.
+fn refetch_stale_days(store: &dyn CalendarStore, year: i32, data: &mut CalendarDays, before: NaiveDate) -> Result<usize> {
+    if !SCRAPED_YEARS.contains(&year) {
+        return Ok(0);
+    }
+
+    let stale: Vec<NaiveDate> = data.iter()
+        .filter(|(_, day)| day.provenance.as_ref().is_none_or(|provenance| provenance.is_stale(before)))
+        .map(|(&date, _)| date)
+        .collect();
+    println!("{} of {} days are stale", stale.len(), data.len());
+
+    for (count, date) in stale.iter().enumerate() {
+        println!("Refetching {}", date);
+        data.insert(*date, fetch_day(year, date.month(), date.day())?);
+
+        // Save progress every month's worth of days
+        if (count + 1) % 31 == 0 {
+            store.save(year, data)?;
+        }
+        std::thread::sleep(FETCH_DELAY);
+    }
+
+    if !stale.is_empty() {
+        store.save(year, data)?;
+    }
+    Ok(stale.len())
+}
+
+/// Removes `name` and the value following it from the arguments, returning the value
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>> {
+    let Some(index) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        anyhow::bail!("Missing value for {}", name);
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
+
+/// Loads a year's data, fetching whatever is missing and refetching stale days
+fn load_year(store: &dyn CalendarStore, year: i32, stale_before: Option<NaiveDate>) -> Result<CalendarDays> {
+    let mut calendar_data = if store.exists(year)? {
+        // Load existing data
+        println!("Loading existing calendar data for year {}", year);
+        store.load(year)?
+    } else {
+        CalendarDays::new()
+    };
+
+    // Check if calendar is complete, if not fetch missing data
+    if !is_year_complete(&calendar_data, year) {
+        println!("Calendar for year {} is incomplete. Fetching missing data...", year);
+        calendar_data = fetch_year_data(store, year)?;
+    }
+
+    if let Some(before) = stale_before {
+        refetch_stale_days(store, year, &mut calendar_data, before)?;
+    }
+    Ok(calendar_data)
+}
+
+/// Writes the rolling iCal files from every year of the window that can be loaded or fetched
+///
+/// The files are always regenerated, since the window moves every January; unchanged
+/// events keep their SEQUENCE, so the output only changes when the events do.
+fn generate_rolling_feed(
+    store: &dyn CalendarStore,
+    years: RangeInclusive<i32>,
+    stale_before: Option<NaiveDate>,
+    ical_options: &IcalOptions,
+) -> Result<()> {
+    let mut data = CalendarDays::new();
+    let mut included = Vec::new();
+    for year in years.clone() {
+        match load_year(store, year, stale_before) {
+            Ok(yearly_data) if is_year_complete(&yearly_data, year) => {
+                data.extend(yearly_data);
+                included.push(year);
+            }
+            Ok(_) => println!("Warning: Calendar data for year {} is incomplete. Leaving it out of the rolling feed.", year),
+            Err(error) => println!("Warning: Could not load year {}: {:#}. Leaving it out of the rolling feed.", year, error),
+        }
+    }
+
+    let (Some(&first), Some(&last)) = (included.first(), included.last()) else {
+        anyhow::bail!("No year of {}..{} is available for the rolling feed", years.start(), years.end());
+    };
+    println!("Generating rolling iCal files for {}..{}", first, last);
+    let changed = generate_rolling_ical(included, &data, ical_options)?;
+    println!("{} event(s) new or changed", changed);
+    Ok(())
+}
+
+/// Prints the RFC 5545 problems found in each file, failing if there are any
+fn validate_ical_files(paths: &[String]) -> Result<()> {
+    let mut invalid = 0;
+    for path in paths {
+        let ics = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read iCal file: {}", path))?;
+        let problems = rfc5545::validate(&ics);
+        if problems.is_empty() {
+            println!("{}: valid", path);
+            continue;
+        }
+        invalid += 1;
+        println!("{}: {} problem(s)", path, problems.len());
+        for problem in problems {
+            println!("  {}", problem);
+        }
+    }
+    if invalid > 0 {
+        anyhow::bail!("{} of {} file(s) are not valid iCalendar", invalid, paths.len());
+    }
+    Ok(())
+}
+
 fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    // Global options; directories given on the command line override the
+    // environment, which overrides the config file
+    let config_path = take_option(&mut args, "--config")?.map(PathBuf::from);
+    let mut config = Config::load(config_path.as_deref())?;
+    config.apply_env();
+    if let Some(dir) = take_option(&mut args, "--data-dir")? {
+        config.data_dir = Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = take_option(&mut args, "--cache-dir")? {
+        config.cache_dir = Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = take_option(&mut args, "--ical-dir")? {
+        config.ical_dir = Some(PathBuf::from(dir));
+    }
+    if args.iter().any(|arg| arg == "--history") {
+        args.retain(|arg| arg != "--history");
+        config.history = true;
+    }
+    config::init(config);
+
+    let store_kind = match take_option(&mut args, "--store")? {
+        Some(name) => StoreKind::parse(&name)?,
+        None => StoreKind::default(),
+    };
+
+    // Commands that only read or compute run without locking
+    let read_only = matches!(args.get(1).map(String::as_str), Some("show" | "coincidences" | "history" | "ical"));
+    let _locks = if read_only {
+        Vec::new()
+    } else {
+        lock_dirs(&[get_data_dir()?, config::cache_dir()?, get_ical_dir()?])?
+    };
+
+    // `orthoterm ical validate FILE...` checks iCal files against RFC 5545
+    if args.get(1).map(String::as_str) == Some("ical") {
+        if args.get(2).map(String::as_str) != Some("validate") || args.len() < 4 {
+            anyhow::bail!("Usage: orthoterm ical validate FILE...");
+        }
+        return validate_ical_files(&args[3..]);
+    }
+
+    // `orthoterm import-json` copies every per-year JSON file into the SQLite store
+    if args.get(1).map(String::as_str) == Some("import-json") {
+        let years = copy_years(&JsonStore, &SqliteStore::open()?)?;
+        println!("Imported {} years into the SQLite store", years.len());
+        return Ok(());
+    }
+
+    let store = open_store(store_kind)?;
+    let store = store.as_ref();
+
+    // `orthoterm show [YYYY-MM-DD]` prints a single day (today by default)
+    if args.get(1).map(String::as_str) == Some("show") {
+        let date = match args.get(2) {
+            Some(date_arg) => NaiveDate::parse_from_str(date_arg, "%Y-%m-%d")
+                .map_err(|_| anyhow::anyhow!("Invalid date (expected YYYY-MM-DD): {}", date_arg))?,
+            None => Local::now().date_naive(),
+        };
+        return show_day(store, date);
+    }
+
+    // `orthoterm check [YEAR|START..END] [--repair]` validates stored years
+    if args.get(1).map(String::as_str) == Some("check") {
+        let repair = args.iter().any(|arg| arg == "--repair");
+        let years = match args.iter().skip(2).find(|arg| *arg != "--repair") {
+            Some(range_arg) => parse_year_range(range_arg)?.collect(),
+            None => store.years()?,
+        };
+        return check_years(store, years, repair);
+    }
+
+    // `orthoterm history YEAR` lists past versions of a year's data and iCal files
+    if args.get(1).map(String::as_str) == Some("history") {
+        let year: i32 = args.get(2)
+            .and_then(|year_arg| year_arg.parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("Usage: orthoterm history YEAR"))?;
+        for (repo, file) in [
+            (history::Repo::data()?, format!("calendar_{}.json", year)),
+            (history::Repo::ical()?, format!("calendar_{}.ics", year)),
+        ] {
+            println!("{}:", file);
+            print!("{}", repo.file_log(&file)?);
+        }
+        return Ok(());
+    }
+
+    // `orthoterm restore YEAR REVISION` brings back a past version of a year's data
+    if args.get(1).map(String::as_str) == Some("restore") {
+        let (Some(year), Some(revision)) = (args.get(2).and_then(|year_arg| year_arg.parse().ok()), args.get(3)) else {
+            anyhow::bail!("Usage: orthoterm restore YEAR REVISION");
+        };
+        let days = restore_yearly_calendar(year, revision)?;
+        println!("Restored {} days of {} from {}; regenerate the iCal file with -i", days, year, revision);
+        return Ok(());
+    }
+
+    // `orthoterm refresh YEAR|START..END [--apply]` compares stored years with the source
+    if args.get(1).map(String::as_str) == Some("refresh") {
+        let apply = args.iter().any(|arg| arg == "--apply");
+        let range_arg = args.iter().skip(2).find(|arg| *arg != "--apply")
+            .ok_or_else(|| anyhow::anyhow!("Usage: orthoterm refresh YEAR|START..END [--apply]"))?;
+        return refresh_years(store, parse_year_range(range_arg)?, apply);
+    }
+
+    // `orthoterm bundle export|import FILE` moves stored years between machines
+    if args.get(1).map(String::as_str) == Some("bundle") {
+        return match (args.get(2).map(String::as_str), args.get(3)) {
+            (Some("export"), path) => {
+                let path = PathBuf::from(path.map_or("orthoterm-bundle.tar.gz", String::as_str));
+                let manifest = export_bundle(store, &path)?;
+                println!("Exported {} years to {:?}", manifest.years.len(), path);
+                Ok(())
+            }
+            (Some("import"), Some(path)) => {
+                let manifest = import_bundle(store, &PathBuf::from(path))?;
+                println!("Imported {} years from {:?}", manifest.years.len(), path);
+                Ok(())
+            }
+            _ => anyhow::bail!("Usage: orthoterm bundle export [FILE] | orthoterm bundle import FILE"),
+        };
+    }
+
+    // `orthoterm coincidences [YEAR|START..END]` lists rare feast concurrences
+    if args.get(1).map(String::as_str) == Some("coincidences") {
+        let years = match args.get(2) {
+            Some(range_arg) => parse_year_range(range_arg)?,
+            None => {
+                let year = Local::now().year();
+                year..=year
+            }
+        };
+        list_coincidences(years);
+        return Ok(());
+    }
+
+    let stale_before = take_option(&mut args, "--stale-before")?
+        .map(|date_arg| NaiveDate::parse_from_str(&date_arg, "%Y-%m-%d")
+            .map_err(|_| anyhow::anyhow!("Invalid date (expected YYYY-MM-DD): {}", date_arg)))
+        .transpose()?;
+
+    let mut ical_options = IcalOptions {
+        alarms: config::config().alarms.clone(),
+        subscription: config::config().subscription.clone(),
+        ..IcalOptions::default()
+    };
+    let templates = &config::config().templates;
+    if let Some(summary) = &templates.summary {
+        ical_options.summary_template = Template::parse(summary)
+            .context("Invalid summary template in the config file")?;
+    }
+    if let Some(description) = &templates.description {
+        ical_options.description_template = Template::parse(description)
+            .context("Invalid description template in the config file")?;
+        ical_options.custom_description = true;
+    }
+    if let Some(feeds) = take_option(&mut args, "--feeds")? {
+        ical_options.feeds = feeds.split(',')
+            .map(|name| Feed::parse(name.trim()))
+            .collect::<Result<_>>()?;
+    }
+
     let mut generate_ical_file = false;
+    let mut rolling = false;
+    let mut force = false;
     let mut year = Local::now().year();
     
     // Parse arguments
@@ -183,6 +705,12 @@ fn main() -> Result<()> {
     while i < args.len() {
         match args[i].as_str() {
             "-i" => generate_ical_file = true,
+            "--force" => force = true,
+            "--rolling" => rolling = true,
+            "--festal-periods" => ical_options.festal_periods = true,
+            "--observances" => ical_options.observances = true,
+            "--fasting-seasons" => ical_options.fasting_seasons = true,
+            "--fasting-days" => ical_options.fasting_days = true,
             year_arg => {
                 if let Ok(y) = year_arg.parse() {
                     year = y;
@@ -192,30 +720,23 @@ fn main() -> Result<()> {
         i += 1;
     }
     
-    let json_exists = calendar_exists(year);
-    let ical_exists = ical_exists(year);
-    
-    let mut calendar_data = if json_exists {
-        // Load existing JSON data
-        println!("Loading existing calendar data for year {}", year);
-        load_calendar(year)?
-    } else {
-        Vec::new()
-    };
-
-    // Check if calendar is complete, if not fetch missing data
-    if !is_year_complete(&calendar_data, year) {
-        println!("Calendar for year {} is incomplete. Fetching missing data...", year);
-        calendar_data = fetch_year_data(year)?;
+    if rolling {
+        let window = &config::config().rolling;
+        let years = year - window.years_before as i32..=year + window.years_after as i32;
+        return generate_rolling_feed(store, years, stale_before, &ical_options);
     }
 
+    let calendar_data = load_year(store, year, stale_before)?;
+
     // Only handle iCal generation if -i flag was provided
     if generate_ical_file {
-        if ical_exists {
-            println!("iCal file for year {} already exists", year);
+        // Regenerate when asked to, or when the data changed after the files were written
+        if !force && ical_up_to_date(year, &ical_options, store.saved_at(year)?)? {
+            println!("iCal files for year {} are up to date (use --force to regenerate them)", year);
         } else if is_year_complete(&calendar_data, year) {
-            println!("Generating iCal file for year {}", year);
-            generate_ical(year, &calendar_data)?;
+            println!("Generating iCal files for year {}", year);
+            let changed = generate_ical(year, &calendar_data, &ical_options)?;
+            println!("{} event(s) new or changed", changed);
         } else {
             println!("Warning: Calendar data for year {} is incomplete. Skipping iCal generation.", year);
         }
@@ -224,3 +745,42 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    const YEAR: i32 = 1850;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(YEAR, month, day).unwrap()
+    }
+
+    #[test]
+    fn repair_fixes_a_broken_computed_year() {
+        let mut days: Vec<OrthoCalendarData> = compute_year_data(YEAR).unwrap().into_values().collect();
+        days.remove(40);
+        days.swap(2, 3);
+        let mut empty = compute_day(date(5, 5)).unwrap();
+        empty.summary.clear();
+        empty.computed.remove("summary");
+        days.push(empty);
+        days.push(compute_day(NaiveDate::from_ymd_opt(YEAR + 1, 1, 1).unwrap()).unwrap());
+
+        let store = MemoryStore::default();
+        store.insert_entries(YEAR, &days);
+        let problems = check_year(YEAR, &store.load_entries(YEAR).unwrap());
+        assert!(problems.contains(&Problem::Missing(date(2, 10))), "{:?}", problems);
+        assert!(problems.contains(&Problem::Duplicate(date(5, 5), 2)), "{:?}", problems);
+        assert!(problems.contains(&Problem::Unsorted), "{:?}", problems);
+
+        repair_year(&store, YEAR, store.load_entries(YEAR).unwrap(), &problems).unwrap();
+        let repaired = store.load_entries(YEAR).unwrap();
+        assert_eq!(check_year(YEAR, &repaired), Vec::new());
+        assert_eq!(repaired.len(), 365);
+        // The complete copy of a duplicated day is kept
+        let pascha = repaired.iter().find(|day| day.date == "1850-05-05").unwrap();
+        assert_eq!(pascha.summary, "Holy Pascha");
+    }
+}
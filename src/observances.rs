@@ -0,0 +1,84 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use crate::paschalion::{julian_fixed_date, pascha};
+
+/// Computed observances that the source's header does not reliably mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Observance {
+    MeatfareSaturday,
+    SecondSaturdayOfLent,
+    ThirdSaturdayOfLent,
+    FourthSaturdayOfLent,
+    Radonitsa,
+    TrinitySaturday,
+    DemetriusSaturday,
+}
+
+impl Observance {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Observance::MeatfareSaturday => "Meatfare Saturday of the Dead",
+            Observance::SecondSaturdayOfLent => "Second Saturday of Great Lent: Commemoration of the Departed",
+            Observance::ThirdSaturdayOfLent => "Third Saturday of Great Lent: Commemoration of the Departed",
+            Observance::FourthSaturdayOfLent => "Fourth Saturday of Great Lent: Commemoration of the Departed",
+            Observance::Radonitsa => "Radonitsa: Paschal Commemoration of the Departed",
+            Observance::TrinitySaturday => "Trinity Saturday of the Dead",
+            Observance::DemetriusSaturday => "Demetrius Saturday of the Dead",
+        }
+    }
+
+    /// Returns true for the Saturdays of the Dead (Soul Saturdays)
+    pub fn is_soul_saturday(&self) -> bool {
+        !matches!(self, Observance::Radonitsa)
+    }
+}
+
+impl fmt::Display for Observance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The Saturday before the feast of St. Demetrius of Thessalonica (Julian October 26)
+fn demetrius_saturday(year: i32) -> Option<NaiveDate> {
+    let feast = julian_fixed_date(year, 10, 26)?;
+    let days_back = match feast.weekday() {
+        Weekday::Sat => 7,
+        weekday => (weekday.num_days_from_monday() as i64 + 2) % 7,
+    };
+    Some(feast - Duration::days(days_back))
+}
+
+/// Returns every computed observance in the given year, in date order
+pub fn observances(year: i32) -> Vec<(NaiveDate, Observance)> {
+    let pascha = pascha(year);
+    let mut found: Vec<(NaiveDate, Observance)> = [
+        (-57, Observance::MeatfareSaturday),
+        (-36, Observance::SecondSaturdayOfLent),
+        (-29, Observance::ThirdSaturdayOfLent),
+        (-22, Observance::FourthSaturdayOfLent),
+        (9, Observance::Radonitsa),
+        (48, Observance::TrinitySaturday),
+    ]
+    .into_iter()
+    .map(|(days, observance)| (pascha + Duration::days(days), observance))
+    .collect();
+
+    if let Some(date) = demetrius_saturday(year) {
+        found.push((date, Observance::DemetriusSaturday));
+    }
+
+    found.sort();
+    found
+}
+
+/// Returns the computed observances that fall on the given date
+pub fn observances_on(date: NaiveDate) -> Vec<Observance> {
+    observances(date.year())
+        .into_iter()
+        .filter(|(observance_date, _)| *observance_date == date)
+        .map(|(_, observance)| observance)
+        .collect()
+}
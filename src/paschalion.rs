@@ -0,0 +1,196 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use std::fmt;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+// Julian Day Number of 0001-01-01 in the proleptic Gregorian calendar, minus one
+const JDN_CE_OFFSET: i32 = 1_721_425;
+
+/// A date in the Julian (Old Style) calendar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct JulianDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl JulianDate {
+    /// Returns true if this date falls on the given Julian month and day
+    pub fn is(&self, month: u32, day: u32) -> bool {
+        self.month == month && self.day == day
+    }
+}
+
+impl fmt::Display for JulianDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}, {}", MONTH_NAMES[self.month as usize - 1], self.day, self.year)
+    }
+}
+
+fn julian_days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year.rem_euclid(4) == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Converts a Julian calendar date to the corresponding Gregorian date
+///
+/// Returns `None` if the Julian date does not exist (e.g. February 30).
+pub fn julian_to_gregorian(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    if !(1..=12).contains(&month) || day == 0 || day > julian_days_in_month(year, month) {
+        return None;
+    }
+
+    let a = (14 - month as i32) / 12;
+    let y = year + 4800 - a;
+    let m = month as i32 + 12 * a - 3;
+    let jdn = day as i32 + (153 * m + 2) / 5 + 365 * y + y.div_euclid(4) - 32083;
+
+    NaiveDate::from_num_days_from_ce_opt(jdn - JDN_CE_OFFSET)
+}
+
+/// Converts a Gregorian date to the corresponding Julian calendar date
+pub fn gregorian_to_julian(date: NaiveDate) -> JulianDate {
+    let jdn = date.num_days_from_ce() + JDN_CE_OFFSET;
+
+    let c = jdn + 32082;
+    let d = (4 * c + 3).div_euclid(1461);
+    let e = c - (1461 * d).div_euclid(4);
+    let m = (5 * e + 2) / 153;
+
+    JulianDate {
+        year: d - 4800 + m / 10,
+        month: (m + 3 - 12 * (m / 10)) as u32,
+        day: (e - (153 * m + 2) / 5 + 1) as u32,
+    }
+}
+
+/// Returns the Gregorian date within `year` on which the Julian `month`/`day` falls
+///
+/// Fixed feasts are kept on the Julian calendar, so e.g. the Julian December 25
+/// of one year falls in January of the following Gregorian year.
+pub fn julian_fixed_date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    [year, year - 1]
+        .into_iter()
+        .filter_map(|julian_year| julian_to_gregorian(julian_year, month, day))
+        .find(|date| date.year() == year)
+}
+
+/// Computes the Gregorian date of Orthodox Pascha for the given year
+///
+/// Uses the Julian computus (Meeus) and converts the result to the Gregorian calendar.
+pub fn pascha(year: i32) -> NaiveDate {
+    let a = year.rem_euclid(4);
+    let b = year.rem_euclid(7);
+    let c = year.rem_euclid(19);
+    let d = (19 * c + 15) % 30;
+    let e = (2 * a + 4 * b - d + 34) % 7;
+    let month = (d + e + 114) / 31;
+    let day = (d + e + 114) % 31 + 1;
+
+    julian_to_gregorian(year, month as u32, day as u32)
+        .expect("Pascha always falls on a valid Julian date")
+}
+
+/// Returns the number of days between `date` and Pascha of the same year
+///
+/// Negative values fall before Pascha (e.g. -7 is Palm Sunday), positive ones after it.
+pub fn days_from_pascha(date: NaiveDate) -> i64 {
+    (date - pascha(date.year())).num_days()
+}
+
+/// Returns the tone of the week (1-8) for the given date
+///
+/// The cycle starts with Tone 1 on Thomas Sunday and runs until Palm Sunday of the
+/// following year. There is no tone of the week during Holy Week and Bright Week.
+pub fn tone(date: NaiveDate) -> Option<u8> {
+    let offset = days_from_pascha(date);
+    if (-7..=6).contains(&offset) {
+        return None;
+    }
+
+    let cycle_year = if offset > 6 { date.year() } else { date.year() - 1 };
+    let thomas_sunday = pascha(cycle_year) + Duration::days(7);
+    let weeks = (date - thomas_sunday).num_days() / 7;
+    Some((weeks % 8) as u8 + 1)
+}
+
+// Days of the Triodion and Pentecostarion, by their distance from Pascha
+const MOVEABLE_DAYS: [(i64, &str); 27] = [
+    (-70, "Sunday of the Publican and the Pharisee"),
+    (-63, "Sunday of the Prodigal Son"),
+    (-57, "Meatfare Saturday"),
+    (-56, "Sunday of the Last Judgment (Meatfare)"),
+    (-49, "Forgiveness Sunday (Cheesefare)"),
+    (-48, "Clean Monday, beginning of Great Lent"),
+    (-42, "Sunday of Orthodoxy"),
+    (-35, "Sunday of St. Gregory Palamas"),
+    (-28, "Sunday of the Veneration of the Cross"),
+    (-21, "Sunday of St. John Climacus"),
+    (-14, "Sunday of St. Mary of Egypt"),
+    (-8, "Lazarus Saturday"),
+    (-7, "Entry of the Lord into Jerusalem (Palm Sunday)"),
+    (-3, "Great and Holy Thursday"),
+    (-2, "Great and Holy Friday"),
+    (-1, "Great and Holy Saturday"),
+    (0, "Holy Pascha"),
+    (7, "Thomas Sunday"),
+    (14, "Sunday of the Myrrhbearing Women"),
+    (21, "Sunday of the Paralytic"),
+    (24, "Mid-Pentecost"),
+    (28, "Sunday of the Samaritan Woman"),
+    (35, "Sunday of the Blind Man"),
+    (39, "Ascension of the Lord"),
+    (42, "Sunday of the Holy Fathers of the First Ecumenical Council"),
+    (49, "Holy Pentecost"),
+    (56, "Sunday of All Saints"),
+];
+
+/// Returns the name of the moveable feast or Sunday falling on the given date, if any
+pub fn moveable_day_name(date: NaiveDate) -> Option<&'static str> {
+    let offset = days_from_pascha(date);
+    MOVEABLE_DAYS.iter()
+        .find(|(days, _)| *days == offset)
+        .map(|(_, name)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn known_pascha_dates() {
+        assert_eq!(pascha(1850), date(1850, 5, 5));
+        assert_eq!(pascha(2000), date(2000, 4, 30));
+        assert_eq!(pascha(2023), date(2023, 4, 16));
+        assert_eq!(pascha(2024), date(2024, 5, 5));
+        assert_eq!(pascha(2025), date(2025, 4, 20));
+        assert_eq!(pascha(2026), date(2026, 4, 12));
+    }
+
+    #[test]
+    fn converts_between_calendars() {
+        assert_eq!(julian_to_gregorian(2024, 12, 25), Some(date(2025, 1, 7)));
+        assert_eq!(julian_to_gregorian(1899, 12, 25), Some(date(1900, 1, 6)));
+        assert_eq!(julian_to_gregorian(2025, 2, 29), None);
+        assert_eq!(gregorian_to_julian(date(2025, 1, 7)), JulianDate { year: 2024, month: 12, day: 25 });
+        assert_eq!(julian_fixed_date(2025, 12, 25), Some(date(2025, 1, 7)));
+    }
+
+    #[test]
+    fn tone_of_the_week() {
+        assert_eq!(tone(date(2025, 4, 27)), Some(1)); // Thomas Sunday
+        assert_eq!(tone(date(2025, 6, 15)), Some(8)); // All Saints
+        assert_eq!(tone(date(2025, 4, 22)), None); // Bright Week
+    }
+}
@@ -0,0 +1,463 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::collections::HashMap;
+
+// Longest line allowed, in octets, not counting the line break
+const MAX_LINE_OCTETS: usize = 75;
+
+// Properties whose values are TEXT and must be escaped
+const TEXT_PROPERTIES: &[&str] = &[
+    "SUMMARY", "DESCRIPTION", "LOCATION", "COMMENT", "CONTACT", "CATEGORIES", "RESOURCES",
+    "NAME", "X-WR-CALNAME", "X-WR-CALDESC", "X-ALT-DESC",
+];
+
+// TEXT properties holding a comma-separated list of values
+const MULTI_VALUED_TEXT_PROPERTIES: &[&str] = &["CATEGORIES", "RESOURCES"];
+
+/// Escapes a TEXT value (RFC 5545, section 3.3.11)
+///
+/// `icalendar` escapes the properties it knows to be TEXT; this is for the others,
+/// such as X- properties.
+pub fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\r', '\n'], "\\n")
+}
+
+/// Formats a DURATION value (RFC 5545, section 3.3.6), e.g. "P1W", "P1D" or "PT12H"
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.num_seconds();
+    if seconds > 0 && seconds % (7 * 86400) == 0 {
+        return format!("P{}W", seconds / (7 * 86400));
+    }
+
+    let mut text = "P".to_string();
+    if seconds >= 86400 {
+        text.push_str(&format!("{}D", seconds / 86400));
+    }
+    let (hours, minutes, seconds) = (seconds % 86400 / 3600, seconds % 3600 / 60, seconds % 60);
+    if hours + minutes + seconds > 0 || text == "P" {
+        text.push('T');
+        for (amount, unit) in [(hours, 'H'), (minutes, 'M'), (seconds, 'S')] {
+            if amount > 0 || (unit == 'S' && text == "PT") {
+                text.push_str(&format!("{}{}", amount, unit));
+            }
+        }
+    }
+    text
+}
+
+/// Folds a content line into lines of at most 75 octets, never splitting a character
+///
+/// Continuation lines start with a space, which counts towards their length.
+pub fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut current = String::new();
+    let mut limit = MAX_LINE_OCTETS;
+    for c in line.chars() {
+        if current.len() + c.len_utf8() > limit {
+            // Some parsers trim each folded line, so trailing whitespace moves to the next one
+            let kept = current.trim_end_matches([' ', '\t']).len();
+            let carried = current.len() - kept;
+            let next = if kept > 0 && carried + c.len_utf8() < limit {
+                current.split_off(kept)
+            } else {
+                String::new()
+            };
+            folded.push_str(&current);
+            folded.push_str("\r\n ");
+            current = next;
+            limit = MAX_LINE_OCTETS - 1;
+        }
+        current.push(c);
+    }
+    folded.push_str(&current);
+    folded
+}
+
+/// Joins folded lines back into content lines, each with the number of its first line
+pub fn unfold(ics: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (index, line) in ics.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some((_, last))) => last.push_str(continuation),
+            _ if line.is_empty() => {}
+            _ => lines.push((index + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+/// Refolds serialized iCalendar data
+///
+/// `icalendar` counts some lines in characters rather than octets, so lines with
+/// multi-byte characters can come out longer than the 75 octets allowed.
+pub fn refold(ics: &str) -> String {
+    unfold(ics).into_iter()
+        .map(|(_, line)| fold_line(&line) + "\r\n")
+        .collect()
+}
+
+/// A content line split into its parts
+struct ContentLine {
+    number: usize,
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl ContentLine {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn is_name(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn is_control(c: char) -> bool {
+    c.is_control() && c != '\t'
+}
+
+/// Parses `name *(";" param) ":" value` (RFC 5545, section 3.1)
+fn parse_content_line(number: usize, line: &str) -> Result<ContentLine, String> {
+    let name_end = line.find([';', ':']).ok_or("missing ':' before the value")?;
+    let name = &line[..name_end];
+    if !is_name(name) {
+        return Err(format!("invalid property name {:?}", name));
+    }
+
+    let mut params = Vec::new();
+    let mut rest = &line[name_end..];
+    while let Some(param) = rest.strip_prefix(';') {
+        let (param_name, mut after) = param.split_once('=')
+            .ok_or_else(|| format!("parameter without a value in {}", name))?;
+        if !is_name(param_name) {
+            return Err(format!("invalid parameter name {:?} in {}", param_name, name));
+        }
+
+        // A parameter may have several comma-separated values, each optionally quoted
+        let mut values = Vec::new();
+        loop {
+            let (value, next) = if let Some(quoted) = after.strip_prefix('"') {
+                let end = quoted.find('"').ok_or_else(|| format!("unclosed quote in {} parameter", param_name))?;
+                (&quoted[..end], &quoted[end + 1..])
+            } else {
+                let end = after.find([';', ':', ',']).unwrap_or(after.len());
+                if after[..end].contains('"') {
+                    return Err(format!("stray quote in {} parameter", param_name));
+                }
+                (&after[..end], &after[end..])
+            };
+            values.push(value);
+            match next.strip_prefix(',') {
+                Some(more) => after = more,
+                None => {
+                    after = next;
+                    break;
+                }
+            }
+        }
+        params.push((param_name.to_ascii_uppercase(), values.join(",")));
+        rest = after;
+    }
+
+    let value = rest.strip_prefix(':').ok_or_else(|| format!("missing ':' after the parameters of {}", name))?;
+    if value.chars().any(is_control) {
+        return Err(format!("control character in the value of {}", name));
+    }
+    Ok(ContentLine { number, name: name.to_ascii_uppercase(), params, value: value.to_string() })
+}
+
+/// Returns a problem with a TEXT value's escapes, if any
+///
+/// Commas must be escaped too, except where they separate the values of a
+/// multi-valued property.
+fn check_text(value: &str, multi_valued: bool) -> Option<String> {
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\\' | ';' | ',' | 'n' | 'N') => {}
+                Some(other) => return Some(format!("invalid escape \\{}", other)),
+                None => return Some("value ends with a lone backslash".to_string()),
+            },
+            ';' => return Some("unescaped ';'".to_string()),
+            ',' if !multi_valued => return Some("unescaped ','".to_string()),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A DATE or DATE-TIME value, for comparing DTSTART with DTEND
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Moment {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime, bool), // Whether the time is in UTC
+}
+
+fn parse_moment(line: &ContentLine) -> Result<Moment, String> {
+    let value = line.value.as_str();
+    if line.param("VALUE").is_some_and(|kind| kind.eq_ignore_ascii_case("DATE")) {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .filter(|_| value.len() == 8)
+            .map(Moment::Date)
+            .ok_or_else(|| format!("{} is not a DATE (YYYYMMDD): {:?}", line.name, value));
+    }
+    let (local, utc) = match value.strip_suffix('Z') {
+        Some(local) => (local, true),
+        None => (value, false),
+    };
+    NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S")
+        .ok()
+        .filter(|_| local.len() == 15)
+        .map(|moment| Moment::DateTime(moment, utc))
+        .ok_or_else(|| format!("{} is not a DATE-TIME (YYYYMMDDTHHMMSS[Z]): {:?}", line.name, value))
+}
+
+/// The components that may appear and the properties each one requires exactly once
+fn required_properties(component: &str) -> Option<&'static [&'static str]> {
+    match component {
+        "VCALENDAR" => Some(&["PRODID", "VERSION"]),
+        "VEVENT" => Some(&["UID", "DTSTAMP", "DTSTART"]),
+        "VALARM" => Some(&["ACTION", "TRIGGER"]),
+        "VTODO" | "VJOURNAL" => Some(&["UID", "DTSTAMP"]),
+        "VFREEBUSY" | "VTIMEZONE" | "STANDARD" | "DAYLIGHT" => Some(&[]),
+        _ if component.starts_with("X-") => Some(&[]),
+        _ => None,
+    }
+}
+
+/// Checks the properties of a finished component
+fn check_component(component: &str, begin: usize, lines: &[ContentLine], problems: &mut Vec<String>) {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for line in lines {
+        *counts.entry(line.name.as_str()).or_default() += 1;
+    }
+    for &name in required_properties(component).unwrap_or_default() {
+        match counts.get(name).copied().unwrap_or(0) {
+            1 => {}
+            0 => problems.push(format!("line {}: {} has no {}", begin, component, name)),
+            _ => problems.push(format!("line {}: {} has more than one {}", begin, component, name)),
+        }
+    }
+
+    let find = |name: &str| lines.iter().find(|line| line.name == name);
+    match component {
+        "VCALENDAR" => {
+            if let Some(version) = find("VERSION").filter(|line| line.value != "2.0") {
+                problems.push(format!("line {}: VERSION must be 2.0, not {:?}", version.number, version.value));
+            }
+        }
+        "VEVENT" => {
+            if find("DTEND").is_some() && find("DURATION").is_some() {
+                problems.push(format!("line {}: VEVENT has both DTEND and DURATION", begin));
+            }
+            let start = find("DTSTART").and_then(|line| parse_moment(line).ok());
+            let end = find("DTEND").and_then(|line| parse_moment(line).ok().map(|moment| (line.number, moment)));
+            if let (Some(start), Some((number, end))) = (start, end) {
+                let same_kind = matches!((start, end), (Moment::Date(_), Moment::Date(_)) | (Moment::DateTime(..), Moment::DateTime(..)));
+                if !same_kind {
+                    problems.push(format!("line {}: DTEND must have the same value type as DTSTART", number));
+                } else if end <= start {
+                    problems.push(format!("line {}: DTEND must be later than DTSTART", number));
+                }
+            }
+        }
+        "VALARM" => {
+            let display = find("ACTION").is_some_and(|line| line.value.eq_ignore_ascii_case("DISPLAY"));
+            if display && find("DESCRIPTION").is_none() {
+                problems.push(format!("line {}: DISPLAY alarm has no DESCRIPTION", begin));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Checks serialized iCalendar data against RFC 5545, returning one message per problem
+///
+/// Covers line breaks and folding, content line syntax, component nesting, required
+/// properties, TEXT escapes and DATE/DATE-TIME values. An empty list means the data is valid.
+pub fn validate(ics: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for (index, line) in ics.split_inclusive('\n').enumerate() {
+        let number = index + 1;
+        if !line.ends_with("\r\n") {
+            problems.push(format!("line {}: not terminated by CRLF", number));
+        }
+        let octets = line.trim_end_matches(['\r', '\n']).len();
+        if octets > MAX_LINE_OCTETS {
+            problems.push(format!("line {}: {} octets long (at most {} allowed)", number, octets, MAX_LINE_OCTETS));
+        }
+    }
+    if ics.starts_with([' ', '\t']) {
+        problems.push("line 1: continuation line with nothing to continue".to_string());
+    }
+
+    // Components still open, with the line they began on and their properties so far
+    let mut open: Vec<(String, usize, Vec<ContentLine>)> = Vec::new();
+    let mut calendars = 0;
+    for (number, text) in unfold(ics) {
+        let line = match parse_content_line(number, &text) {
+            Ok(line) => line,
+            Err(problem) => {
+                problems.push(format!("line {}: {}", number, problem));
+                continue;
+            }
+        };
+
+        match line.name.as_str() {
+            "BEGIN" => {
+                let component = line.value.to_ascii_uppercase();
+                if open.is_empty() && component != "VCALENDAR" {
+                    problems.push(format!("line {}: {} outside of VCALENDAR", number, component));
+                } else if required_properties(&component).is_none() {
+                    problems.push(format!("line {}: unknown component {}", number, component));
+                }
+                if component == "VCALENDAR" {
+                    calendars += 1;
+                    if !open.is_empty() {
+                        problems.push(format!("line {}: VCALENDAR nested in {}", number, open[0].0));
+                    }
+                }
+                open.push((component, number, Vec::new()));
+            }
+            "END" => {
+                let component = line.value.to_ascii_uppercase();
+                match open.pop() {
+                    Some((name, begin, lines)) if name == component => {
+                        check_component(&name, begin, &lines, &mut problems);
+                    }
+                    Some((name, begin, _)) => {
+                        problems.push(format!("line {}: END:{} closes {} begun on line {}", number, component, name, begin));
+                    }
+                    None => problems.push(format!("line {}: END:{} without BEGIN", number, component)),
+                }
+            }
+            name => {
+                if TEXT_PROPERTIES.contains(&name) {
+                    if let Some(problem) = check_text(&line.value, MULTI_VALUED_TEXT_PROPERTIES.contains(&name)) {
+                        problems.push(format!("line {}: {} in {}", number, problem, name));
+                    }
+                }
+                match name {
+                    "DTSTART" | "DTEND" | "RECURRENCE-ID" => {
+                        if let Err(problem) = parse_moment(&line) {
+                            problems.push(format!("line {}: {}", number, problem));
+                        }
+                    }
+                    "DTSTAMP" | "LAST-MODIFIED" | "CREATED" if !matches!(parse_moment(&line), Ok(Moment::DateTime(_, true))) => {
+                        problems.push(format!("line {}: {} must be a UTC DATE-TIME: {:?}", number, name, line.value));
+                    }
+                    "SEQUENCE" if line.value.parse::<u32>().is_err() => {
+                        problems.push(format!("line {}: SEQUENCE is not a number: {:?}", number, line.value));
+                    }
+                    _ => {}
+                }
+                match open.last_mut() {
+                    Some((_, _, lines)) => lines.push(line),
+                    None => problems.push(format!("line {}: {} outside of any component", number, name)),
+                }
+            }
+        }
+    }
+
+    for (component, begin, _) in open.iter().rev() {
+        problems.push(format!("line {}: {} is never closed", begin, component));
+    }
+    if calendars == 0 {
+        problems.push("no VCALENDAR found".to_string());
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:test\r\nBEGIN:VEVENT\r\n\
+        UID:1@test\r\nDTSTAMP:20250101T000000Z\r\nDTSTART;VALUE=DATE:20250101\r\n\
+        DTEND;VALUE=DATE:20250102\r\nSUMMARY:Feast\\, vigil\\; fast\r\nCATEGORIES:Feasts,Fasting\r\n\
+        END:VEVENT\r\nEND:VCALENDAR\r\n";
+
+    #[test]
+    fn escapes_text() {
+        assert_eq!(escape_text("a\\b, c; d\r\ne\nf"), r"a\\b\, c\; d\ne\nf");
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(Duration::weeks(2)), "P2W");
+        assert_eq!(format_duration(Duration::days(1)), "P1D");
+        assert_eq!(format_duration(Duration::days(1) + Duration::hours(6)), "P1DT6H");
+        assert_eq!(format_duration(Duration::minutes(90)), "PT1H30M");
+        assert_eq!(format_duration(Duration::zero()), "PT0S");
+    }
+
+    #[test]
+    fn folds_at_75_octets_without_splitting_characters() {
+        for text in ["x".repeat(200), "Святитель Николай, ".repeat(20), "ἅγιος ὁ θεός ☦ ".repeat(15)] {
+            let line = format!("DESCRIPTION:{}", text);
+            let folded = fold_line(&line);
+            for physical in folded.split("\r\n") {
+                assert!(physical.len() <= MAX_LINE_OCTETS, "{} octets: {:?}", physical.len(), physical);
+            }
+            assert_eq!(unfold(&folded), vec![(1, line)]);
+        }
+    }
+
+    #[test]
+    fn folding_keeps_whitespace_off_line_ends() {
+        let line = format!("SUMMARY:{}", "word ".repeat(40).trim_end());
+        for physical in fold_line(&line).split("\r\n") {
+            assert!(!physical.ends_with(' '), "{:?}", physical);
+        }
+    }
+
+    #[test]
+    fn refolds_overlong_lines() {
+        let long = format!("BEGIN:VCALENDAR\r\nX-NOTE:{}\r\nEND:VCALENDAR\r\n", "é".repeat(60));
+        let refolded = refold(&long);
+        assert!(refolded.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+        let lines = |ics: &str| unfold(ics).into_iter().map(|(_, line)| line).collect::<Vec<_>>();
+        assert_eq!(lines(&refolded), lines(&long));
+    }
+
+    #[test]
+    fn accepts_valid_calendar() {
+        assert_eq!(validate(VALID), Vec::<String>::new());
+        assert_eq!(validate(&refold(VALID)), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_problems() {
+        let cases = [
+            (VALID.replace("\r\n", "\n"), "not terminated by CRLF"),
+            (VALID.replace("Feast", &"Feast".repeat(20)), "octets long"),
+            (VALID.replace("DTEND;VALUE=DATE:20250102", "DTEND;VALUE=DATE:20250101"), "DTEND must be later"),
+            (VALID.replace("UID:1@test\r\n", ""), "VEVENT has no UID"),
+            (VALID.replace("PRODID:test\r\n", ""), "VCALENDAR has no PRODID"),
+            (VALID.replace("\\,", "\\x"), "invalid escape \\x"),
+            (VALID.replace("\\,", ","), "unescaped ','"),
+            (VALID.replace("\\;", ";"), "unescaped ';'"),
+            (VALID.replace("Feasts,Fasting", "Feasts;Fasting"), "unescaped ';' in CATEGORIES"),
+            (VALID.replace("DTSTAMP:20250101T000000Z", "DTSTAMP:20250101T000000"), "must be a UTC DATE-TIME"),
+            (VALID.replace("DTSTART;VALUE=DATE:20250101", "DTSTART;VALUE=DATE:2025-01-01"), "is not a DATE"),
+            (VALID.replace("END:VEVENT\r\n", ""), "closes VEVENT"),
+            (VALID.replace("SUMMARY:", "SUMMARY "), "invalid property name"),
+            (VALID.replace("VERSION:2.0", "VERSION:1.0"), "VERSION must be 2.0"),
+        ];
+        for (ics, expected) in cases {
+            let problems = validate(&ics);
+            assert!(problems.iter().any(|problem| problem.contains(expected)), "{:?} not in {:?}", expected, problems);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use crate::json::OrthoCalendarData;
+
+/// Version of the on-disk calendar format written by this build
+///
+/// Version 0 is the original bare array of days; version 1 wraps the days in an
+/// object carrying the schema version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct StoredCalendar<'a> {
+    schema_version: u32,
+    days: Vec<&'a OrthoCalendarData>,
+}
+
+type Migration = fn(Value) -> Result<Value>;
+
+// MIGRATIONS[n] upgrades a file from schema version n to n + 1
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [
+    migrate_v0_to_v1,
+];
+
+fn migrate_v0_to_v1(value: Value) -> Result<Value> {
+    Ok(json!({ "schema_version": 1, "days": value }))
+}
+
+/// Returns the schema version of a stored calendar
+pub fn schema_version(value: &Value) -> Result<u32> {
+    match value {
+        Value::Array(_) => Ok(0),
+        Value::Object(object) => object.get("schema_version")
+            .and_then(Value::as_u64)
+            .map(|version| version as u32)
+            .ok_or_else(|| anyhow::anyhow!("Calendar file has no schema_version")),
+        _ => anyhow::bail!("Calendar file is neither an array nor an object"),
+    }
+}
+
+/// Upgrades a stored calendar to the current schema version
+///
+/// Fails with a clear error for files written by a newer orthoterm.
+pub fn migrate(mut value: Value) -> Result<Value> {
+    let version = schema_version(&value)?;
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "Calendar file uses schema version {}, but this orthoterm only supports up to version {}. Please upgrade orthoterm.",
+            version, SCHEMA_VERSION
+        );
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        value = migration(value)
+            .with_context(|| format!("Failed to migrate calendar from schema version {}", from))?;
+    }
+    Ok(value)
+}
+
+/// Parses a stored calendar of any supported version
+///
+/// Returns the days and the version the file was written with.
+pub fn parse_calendar(contents: &str) -> Result<(Vec<OrthoCalendarData>, u32)> {
+    let value: Value = serde_json::from_str(contents)?;
+    let version = schema_version(&value)?;
+    let mut value = migrate(value)?;
+
+    let days = value.get_mut("days")
+        .map(Value::take)
+        .ok_or_else(|| anyhow::anyhow!("Calendar file has no days"))?;
+    Ok((serde_json::from_value(days)?, version))
+}
+
+/// Serializes days in the current schema version
+pub fn serialize_calendar<'a>(days: impl IntoIterator<Item = &'a OrthoCalendarData>) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&StoredCalendar {
+        schema_version: SCHEMA_VERSION,
+        days: days.into_iter().collect(),
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A day as stored before any of the computed fields existed
+    const DAY: &str = r#"{
+        "date": "2025-01-07",
+        "julian_date": "December 25, 2024",
+        "summary": "The Nativity of Our Lord",
+        "liturgical_notes": "Fast-free",
+        "lives": ["The Nativity of Our Lord God and Savior Jesus Christ"],
+        "troparia": [],
+        "scripture": ["Matthew 1:18-25"]
+    }"#;
+
+    fn version_0() -> String {
+        format!("[{}]", DAY)
+    }
+
+    fn version_1() -> String {
+        format!(r#"{{"schema_version": 1, "days": [{}]}}"#, DAY)
+    }
+
+    #[test]
+    fn loads_every_version() {
+        for (contents, version) in [(version_0(), 0), (version_1(), 1)] {
+            let (days, found) = parse_calendar(&contents).unwrap();
+            assert_eq!(found, version);
+            assert_eq!(days.len(), 1);
+            assert_eq!(days[0].summary, "The Nativity of Our Lord");
+            assert_eq!(days[0].scripture, ["Matthew 1:18-25"]);
+        }
+    }
+
+    #[test]
+    fn upgrades_to_the_current_version() {
+        for contents in [version_0(), version_1()] {
+            let (days, _) = parse_calendar(&contents).unwrap();
+            let upgraded = serialize_calendar(&days).unwrap();
+            let value: Value = serde_json::from_str(&upgraded).unwrap();
+            assert_eq!(schema_version(&value).unwrap(), SCHEMA_VERSION);
+
+            let (reloaded, version) = parse_calendar(&upgraded).unwrap();
+            assert_eq!(version, SCHEMA_VERSION);
+            assert_eq!(reloaded[0].julian_date, days[0].julian_date);
+        }
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let newer = format!(r#"{{"schema_version": {}, "days": []}}"#, SCHEMA_VERSION + 1);
+        let error = parse_calendar(&newer).unwrap_err().to_string();
+        assert!(error.contains("Please upgrade orthoterm"), "{}", error);
+        assert!(parse_calendar(r#"{"days": []}"#).is_err());
+    }
+}
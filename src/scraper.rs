@@ -55,7 +55,7 @@ pub async fn fetch_page(url: &str) -> Result<String> {
             }
             Err(e) => {
                 attempts += 1;
-                let is_timeout = matches!(e.status(), None) && e.is_timeout();
+                let is_timeout = e.status().is_none() && e.is_timeout();
                 println!("Request failed ({}): {}. Attempt {}", 
                     if is_timeout { "timeout" } else { "error" },
                     e,
@@ -63,7 +63,7 @@ pub async fn fetch_page(url: &str) -> Result<String> {
                 );
                 
                 // For timeouts or server errors, keep retrying indefinitely
-                if is_timeout || e.status().map_or(false, |s| s.is_server_error()) {
+                if is_timeout || e.status().is_some_and(|s| s.is_server_error()) {
                     let delay = INITIAL_RETRY_DELAY * (2_u32.pow(attempts.min(5) - 1));
                     println!("Retrying in {} seconds...", delay.as_secs());
                     thread::sleep(delay);
@@ -0,0 +1,286 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use crate::json::{get_data_dir, CalendarDays, DataOrigin, OrthoCalendarData, Provenance};
+use crate::store::CalendarStore;
+
+// SQLITE_MIGRATIONS[n] upgrades the database from `user_version` n to n + 1
+const SQLITE_MIGRATIONS: [&str; 5] = [
+    "
+    CREATE TABLE IF NOT EXISTS days (
+        date             TEXT PRIMARY KEY,  -- YYYY-MM-DD
+        year             INTEGER NOT NULL,
+        julian_date      TEXT NOT NULL,
+        summary          TEXT NOT NULL,
+        liturgical_notes TEXT NOT NULL,
+        origin           TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS days_year ON days (year);
+
+    CREATE TABLE IF NOT EXISTS commemorations (
+        date     TEXT NOT NULL REFERENCES days (date) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        text     TEXT NOT NULL,
+        PRIMARY KEY (date, position)
+    );
+
+    CREATE TABLE IF NOT EXISTS readings (
+        date     TEXT NOT NULL REFERENCES days (date) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        text     TEXT NOT NULL,
+        PRIMARY KEY (date, position)
+    );
+
+    CREATE TABLE IF NOT EXISTS hymns (
+        date     TEXT NOT NULL REFERENCES days (date) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        text     TEXT NOT NULL,
+        PRIMARY KEY (date, position)
+    );
+
+    CREATE TABLE IF NOT EXISTS fasting (
+        date  TEXT PRIMARY KEY REFERENCES days (date) ON DELETE CASCADE,
+        level TEXT NOT NULL
+    );
+    ",
+    "
+    CREATE TABLE IF NOT EXISTS saved_years (
+        year     INTEGER PRIMARY KEY,
+        saved_at TEXT NOT NULL  -- RFC 3339, UTC
+    );
+    ",
+    "
+    CREATE TABLE IF NOT EXISTS provenance (
+        date           TEXT PRIMARY KEY REFERENCES days (date) ON DELETE CASCADE,
+        fetched_at     TEXT NOT NULL,  -- RFC 3339, UTC
+        source         TEXT NOT NULL,
+        parser_version INTEGER NOT NULL,
+        content_sha256 TEXT NOT NULL
+    );
+    ",
+    "
+    CREATE TABLE IF NOT EXISTS computed_fields (
+        date  TEXT NOT NULL REFERENCES days (date) ON DELETE CASCADE,
+        field TEXT NOT NULL,
+        PRIMARY KEY (date, field)
+    );
+    ",
+    // The fasting level is recomputed from the date on load, so it is not stored
+    "
+    DROP TABLE IF EXISTS fasting;
+    ",
+];
+
+// Tables holding a day's ordered lists: lives, scripture and troparia
+const LIST_TABLES: [&str; 3] = ["commemorations", "readings", "hymns"];
+
+pub fn get_database_path() -> Result<PathBuf> {
+    let mut path = get_data_dir()?;
+    path.push("orthoterm.db");
+    Ok(path)
+}
+
+/// Applies any pending schema migrations, tracked in SQLite's `user_version`
+fn migrate(connection: &Connection) -> Result<()> {
+    let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > SQLITE_MIGRATIONS.len() {
+        anyhow::bail!(
+            "Database uses schema version {}, but this orthoterm only supports up to version {}. Please upgrade orthoterm.",
+            version, SQLITE_MIGRATIONS.len()
+        );
+    }
+
+    for (from, migration) in SQLITE_MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = connection.unchecked_transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", from + 1)?;
+        transaction.commit()?;
+    }
+    Ok(())
+}
+
+/// Converts a unit enum to the string serde would use for it
+fn to_sql_text<T: Serialize>(value: &T) -> Result<String> {
+    serde_json::to_value(value)?
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("Value does not serialize to a string"))
+}
+
+fn from_sql_text<T: DeserializeOwned>(text: String) -> Result<T> {
+    Ok(serde_json::from_value(serde_json::Value::String(text))?)
+}
+
+/// Stores all years in a single SQLite database in the data directory
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    pub fn open() -> Result<Self> {
+        let path = get_database_path()?;
+        let connection = Connection::open(&path)
+            .with_context(|| format!("Failed to open database: {:?}", path))?;
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+        migrate(&connection)
+            .with_context(|| format!("Failed to migrate database: {:?}", path))?;
+        Ok(Self { connection })
+    }
+
+    fn load_list(&self, table: &str, date: &str) -> Result<Vec<String>> {
+        let mut statement = self.connection.prepare_cached(
+            &format!("SELECT text FROM {} WHERE date = ?1 ORDER BY position", table)
+        )?;
+        let rows = statement.query_map([date], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<String>>>()?)
+    }
+
+    fn load_computed_fields(&self, date: &str) -> Result<BTreeSet<String>> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT field FROM computed_fields WHERE date = ?1"
+        )?;
+        let rows = statement.query_map([date], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<BTreeSet<String>>>()?)
+    }
+
+    fn load_provenance(&self, date: &str) -> Result<Option<Provenance>> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT fetched_at, source, parser_version, content_sha256 FROM provenance WHERE date = ?1"
+        )?;
+        let row = statement.query_row([date], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, u32>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        }).optional()?;
+
+        row.map(|(fetched_at, source, parser_version, content_sha256)| Ok(Provenance {
+            fetched_at: DateTime::parse_from_rfc3339(&fetched_at)?.with_timezone(&Utc),
+            source,
+            parser_version,
+            content_sha256,
+        })).transpose()
+    }
+}
+
+impl CalendarStore for SqliteStore {
+    fn exists(&self, year: i32) -> Result<bool> {
+        Ok(self.connection
+            .query_row("SELECT EXISTS (SELECT 1 FROM days WHERE year = ?1)", [year], |row| row.get(0))?)
+    }
+
+    fn load_entries(&self, year: i32) -> Result<Vec<OrthoCalendarData>> {
+        let mut statement = self.connection.prepare(
+            "SELECT date, julian_date, summary, liturgical_notes, origin
+             FROM days WHERE year = ?1 ORDER BY date"
+        )?;
+        let rows = statement.query_map([year], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+
+        let mut calendar = Vec::new();
+        for row in rows {
+            let (date, julian_date, summary, liturgical_notes, origin) = row?;
+            let mut day = OrthoCalendarData {
+                provenance: self.load_provenance(&date)?,
+                computed: self.load_computed_fields(&date)?,
+                lives: self.load_list("commemorations", &date)?,
+                scripture: self.load_list("readings", &date)?,
+                troparia: self.load_list("hymns", &date)?,
+                date,
+                julian_date,
+                summary,
+                liturgical_notes,
+                liturgy: None,
+                festal: Vec::new(),
+                coincidences: Vec::new(),
+                fasting: None,
+                tone: None,
+                observances: Vec::new(),
+                origin: from_sql_text::<DataOrigin>(origin)?,
+            };
+            day.annotate();
+            calendar.push(day);
+        }
+        Ok(calendar)
+    }
+
+    fn save(&self, year: i32, data: &CalendarDays) -> Result<()> {
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute("DELETE FROM days WHERE year = ?1", [year])?;
+
+        for day in data.values() {
+            // A day stored under another year is moved, along with its lists
+            transaction.execute("DELETE FROM days WHERE date = ?1", [&day.date])?;
+            transaction.execute(
+                "INSERT INTO days (date, year, julian_date, summary, liturgical_notes, origin)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![day.date, year, day.julian_date, day.summary, day.liturgical_notes, to_sql_text(&day.origin)?],
+            )?;
+
+            for (table, items) in LIST_TABLES.iter().zip([&day.lives, &day.scripture, &day.troparia]) {
+                for (position, text) in items.iter().enumerate() {
+                    transaction.execute(
+                        &format!("INSERT INTO {} (date, position, text) VALUES (?1, ?2, ?3)", table),
+                        params![day.date, position as i64, text],
+                    )?;
+                }
+            }
+
+            for field in &day.computed {
+                transaction.execute(
+                    "INSERT INTO computed_fields (date, field) VALUES (?1, ?2)",
+                    params![day.date, field],
+                )?;
+            }
+
+            if let Some(provenance) = &day.provenance {
+                transaction.execute(
+                    "INSERT INTO provenance (date, fetched_at, source, parser_version, content_sha256)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        day.date,
+                        provenance.fetched_at.to_rfc3339(),
+                        provenance.source,
+                        provenance.parser_version,
+                        provenance.content_sha256,
+                    ],
+                )?;
+            }
+        }
+
+        transaction.execute(
+            "INSERT OR REPLACE INTO saved_years (year, saved_at) VALUES (?1, ?2)",
+            params![year, Utc::now().to_rfc3339()],
+        )?;
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn years(&self) -> Result<Vec<i32>> {
+        let mut statement = self.connection.prepare("SELECT DISTINCT year FROM days ORDER BY year")?;
+        let rows = statement.query_map([], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<i32>>>()?)
+    }
+
+    fn saved_at(&self, year: i32) -> Result<Option<DateTime<Utc>>> {
+        let saved_at: Option<String> = self.connection
+            .query_row("SELECT saved_at FROM saved_years WHERE year = ?1", [year], |row| row.get(0))
+            .optional()?;
+        saved_at
+            .map(|text| Ok(DateTime::parse_from_rfc3339(&text)?.with_timezone(&Utc)))
+            .transpose()
+    }
+}
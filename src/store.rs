@@ -0,0 +1,122 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use crate::json::{index_by_date, CalendarDays, JsonStore, OrthoCalendarData};
+use crate::sqlite::SqliteStore;
+use crate::history;
+
+/// Persistent storage for yearly calendar data
+pub trait CalendarStore {
+    /// Returns true if any data is stored for the year
+    fn exists(&self, year: i32) -> Result<bool>;
+
+    /// Loads the year's entries exactly as stored (duplicates and all), annotated
+    /// with the computed fields
+    fn load_entries(&self, year: i32) -> Result<Vec<OrthoCalendarData>>;
+
+    /// Loads the year keyed by date
+    fn load(&self, year: i32) -> Result<CalendarDays> {
+        Ok(index_by_date(self.load_entries(year)?))
+    }
+
+    /// Replaces the stored data for the year
+    fn save(&self, year: i32, data: &CalendarDays) -> Result<()>;
+
+    /// Lists the years with stored data, in ascending order
+    fn years(&self) -> Result<Vec<i32>>;
+
+    /// Returns when the year was last saved, if known
+    fn saved_at(&self, year: i32) -> Result<Option<DateTime<Utc>>>;
+}
+
+/// The available storage backends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StoreKind {
+    #[default]
+    Json,
+    Sqlite,
+}
+
+impl StoreKind {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "json" => Ok(StoreKind::Json),
+            "sqlite" => Ok(StoreKind::Sqlite),
+            _ => anyhow::bail!("Unknown store: {} (expected json or sqlite)", name),
+        }
+    }
+}
+
+/// Opens the store of the given kind in the data directory
+///
+/// History is only recorded for the JSON store, so enabling it with the SQLite
+/// store is an error rather than silently recording nothing.
+pub fn open_store(kind: StoreKind) -> Result<Box<dyn CalendarStore>> {
+    if kind == StoreKind::Sqlite && history::enabled() {
+        anyhow::bail!(
+            "History is only recorded for the JSON store; disable history (--history or history = true) or use --store json"
+        );
+    }
+
+    Ok(match kind {
+        StoreKind::Json => Box::new(JsonStore),
+        StoreKind::Sqlite => Box::new(SqliteStore::open()?),
+    })
+}
+
+/// Copies every year from one store into another, returning the years copied
+pub fn copy_years(from: &dyn CalendarStore, to: &dyn CalendarStore) -> Result<Vec<i32>> {
+    let years = from.years()?;
+    for &year in &years {
+        let data = from.load(year)?;
+        println!("Importing {} days for year {}", data.len(), year);
+        to.save(year, &data)?;
+    }
+    Ok(years)
+}
+
+/// A store kept in memory, for tests
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryStore {
+    years: std::cell::RefCell<std::collections::BTreeMap<i32, String>>,
+}
+
+#[cfg(test)]
+impl MemoryStore {
+    /// Stores entries exactly as given, duplicates and all
+    pub fn insert_entries(&self, year: i32, entries: &[OrthoCalendarData]) {
+        let json = crate::schema::serialize_calendar(entries).unwrap();
+        self.years.borrow_mut().insert(year, json);
+    }
+}
+
+#[cfg(test)]
+impl CalendarStore for MemoryStore {
+    fn exists(&self, year: i32) -> Result<bool> {
+        Ok(self.years.borrow().contains_key(&year))
+    }
+
+    fn load_entries(&self, year: i32) -> Result<Vec<OrthoCalendarData>> {
+        let years = self.years.borrow();
+        let json = years.get(&year).ok_or_else(|| anyhow::anyhow!("No data for {}", year))?;
+        let (mut days, _) = crate::schema::parse_calendar(json)?;
+        for day in &mut days {
+            day.annotate();
+        }
+        Ok(days)
+    }
+
+    fn save(&self, year: i32, data: &CalendarDays) -> Result<()> {
+        let json = crate::schema::serialize_calendar(data.values())?;
+        self.years.borrow_mut().insert(year, json);
+        Ok(())
+    }
+
+    fn years(&self) -> Result<Vec<i32>> {
+        Ok(self.years.borrow().keys().copied().collect())
+    }
+
+    fn saved_at(&self, _year: i32) -> Result<Option<DateTime<Utc>>> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,352 @@
+use anyhow::Result;
+use crate::ical::sanitize_text;
+use crate::json::OrthoCalendarData;
+
+/// The SUMMARY layout used unless the config file sets one
+pub const DEFAULT_SUMMARY: &str = "{summary}";
+
+/// The DESCRIPTION layout used unless the config file sets one
+pub const DEFAULT_DESCRIPTION: &str = "({julian_date}){#festal}
+{festal}{/festal}{#coincidences}
+{coincidences}{/coincidences}{#observances}
+{observances}{/observances}{#liturgy}
+{liturgy}{/liturgy}{#liturgical_notes}
+
+Notes:
+{liturgical_notes}{/liturgical_notes}
+
+Saints:
+{lives}
+
+Troparia:
+{troparia}
+
+Scripture:
+{scripture}";
+
+/// A day field that can be placed in a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Date,
+    JulianDate,
+    Summary,
+    LiturgicalNotes,
+    Lives,
+    Troparia,
+    Scripture,
+    Liturgy,
+    Festal,
+    Coincidences,
+    Fasting,
+    Tone,
+    Observances,
+    Origin,
+    Computed,
+    Provenance,
+}
+
+impl Field {
+    const ALL: [(&'static str, Field); 16] = [
+        ("date", Field::Date),
+        ("julian_date", Field::JulianDate),
+        ("summary", Field::Summary),
+        ("liturgical_notes", Field::LiturgicalNotes),
+        ("lives", Field::Lives),
+        ("troparia", Field::Troparia),
+        ("scripture", Field::Scripture),
+        ("liturgy", Field::Liturgy),
+        ("festal", Field::Festal),
+        ("coincidences", Field::Coincidences),
+        ("fasting", Field::Fasting),
+        ("tone", Field::Tone),
+        ("observances", Field::Observances),
+        ("origin", Field::Origin),
+        ("computed", Field::Computed),
+        ("provenance", Field::Provenance),
+    ];
+
+    fn parse(name: &str) -> Result<Self> {
+        Field::ALL.iter()
+            .find(|(field_name, _)| *field_name == name)
+            .map(|(_, field)| *field)
+            .ok_or_else(|| anyhow::anyhow!(
+                "Unknown template field {{{}}} (expected one of: {})",
+                name,
+                Field::ALL.map(|(field_name, _)| field_name).join(", ")
+            ))
+    }
+
+    /// Renders the field as text; lists are one item per line and missing values are empty
+    fn value(&self, day: &OrthoCalendarData) -> String {
+        fn lines(items: &[impl ToString]) -> String {
+            items.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n")
+        }
+
+        match self {
+            Field::Date => day.date.clone(),
+            Field::JulianDate => day.julian_date.clone(),
+            Field::Summary => day.summary.clone(),
+            Field::LiturgicalNotes => day.liturgical_notes.clone(),
+            Field::Lives => sanitize_text(&day.lives.join("\n")),
+            // Troparia are separated by a blank line and lose their "Troparia" heading
+            Field::Troparia => {
+                let troparia: Vec<String> = day.troparia.iter()
+                    .map(|t| match t.strip_prefix("Troparia") {
+                        Some(rest) => rest.trim().to_string(),
+                        None => t.to_string(),
+                    })
+                    .collect();
+                sanitize_text(&troparia.join("\n")).replace('\n', "\n\n")
+            }
+            Field::Scripture => sanitize_text(&day.scripture.join("\n")),
+            Field::Liturgy => day.liturgy.map(|liturgy| liturgy.to_string()).unwrap_or_default(),
+            Field::Festal => lines(&day.festal),
+            Field::Coincidences => lines(&day.coincidences),
+            Field::Fasting => day.fasting.map(|fasting| fasting.to_string()).unwrap_or_default(),
+            Field::Tone => day.tone.map(|tone| tone.to_string()).unwrap_or_default(),
+            Field::Observances => lines(&day.observances),
+            Field::Origin => serde_json::to_value(day.origin).ok()
+                .and_then(|value| value.as_str().map(str::to_string))
+                .unwrap_or_default(),
+            Field::Computed => day.computed.iter().cloned().collect::<Vec<_>>().join(", "),
+            Field::Provenance => day.provenance.as_ref()
+                .map(|provenance| format!("{} ({})", provenance.source, provenance.fetched_at.format("%Y-%m-%d")))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Field(Field),
+    /// Rendered only when the field is non-empty, or only when it is empty if `inverted`
+    Section { field: Field, inverted: bool, body: Vec<Part> },
+}
+
+/// A layout for event text with placeholders for day fields
+///
+/// - `{field}` is replaced by the field's value
+/// - `{#field}...{/field}` is kept only when the field is non-empty
+/// - `{^field}...{/field}` is kept only when the field is empty
+/// - `{{` and `}}` stand for literal braces
+#[derive(Debug, Clone)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut rest = text;
+        let parts = parse_parts(&mut rest, None)?;
+        Ok(Self { parts })
+    }
+
+    pub fn render(&self, day: &OrthoCalendarData) -> String {
+        let mut out = String::new();
+        render_parts(&self.parts, day, &mut out);
+        out
+    }
+}
+
+/// Parses parts up to the end of the input, or up to the closing tag of `section`
+/// (its field and whether it is inverted)
+fn parse_parts(rest: &mut &str, section: Option<(Field, bool)>) -> Result<Vec<Part>> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+
+    loop {
+        let Some(index) = rest.find(['{', '}']) else {
+            text.push_str(rest);
+            *rest = "";
+            break;
+        };
+        text.push_str(&rest[..index]);
+        let tail = &rest[index..];
+
+        if let Some(after) = tail.strip_prefix("{{").or_else(|| tail.strip_prefix("}}")) {
+            text.push_str(&tail[..1]);
+            *rest = after;
+            continue;
+        }
+        if tail.starts_with('}') {
+            anyhow::bail!("Unmatched '}}' in template (write '}}}}' for a literal brace)");
+        }
+
+        let end = tail.find('}')
+            .ok_or_else(|| anyhow::anyhow!("Unclosed '{{' in template"))?;
+        let tag = &tail[1..end];
+        *rest = &tail[end + 1..];
+
+        if !text.is_empty() {
+            parts.push(Part::Text(std::mem::take(&mut text)));
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            let field = Field::parse(name.trim())?;
+            if section.map(|(open, _)| open) != Some(field) {
+                anyhow::bail!("Unexpected {{/{}}} in template", name.trim());
+            }
+            return Ok(parts);
+        } else if let Some(name) = tag.strip_prefix('#') {
+            let field = Field::parse(name.trim())?;
+            let body = parse_parts(rest, Some((field, false)))?;
+            parts.push(Part::Section { field, inverted: false, body });
+        } else if let Some(name) = tag.strip_prefix('^') {
+            let field = Field::parse(name.trim())?;
+            let body = parse_parts(rest, Some((field, true)))?;
+            parts.push(Part::Section { field, inverted: true, body });
+        } else {
+            parts.push(Part::Field(Field::parse(tag.trim())?));
+        }
+    }
+
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    if let Some((field, inverted)) = section {
+        let name = Field::ALL.iter().find(|(_, f)| *f == field).map_or("", |(name, _)| name);
+        let sigil = if inverted { '^' } else { '#' };
+        anyhow::bail!("Section {{{}{}}} is never closed with {{/{}}}", sigil, name, name);
+    }
+    Ok(parts)
+}
+
+fn render_parts(parts: &[Part], day: &OrthoCalendarData, out: &mut String) {
+    for part in parts {
+        match part {
+            Part::Text(text) => out.push_str(text),
+            Part::Field(field) => out.push_str(&field.value(day)),
+            Part::Section { field, inverted, body } => {
+                if field.value(day).is_empty() == *inverted {
+                    render_parts(body, day, out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computed::compute_year_data;
+
+    fn day(notes: &str, lives: &[&str], troparia: &[&str]) -> OrthoCalendarData {
+        let strings = |items: &[&str]| items.iter().map(ToString::to_string).collect();
+        OrthoCalendarData::new(
+            "January 7, 2025".to_string(),
+            "December 25, 2024".to_string(),
+            "Nativity of Christ".to_string(),
+            notes.to_string(),
+            strings(lives),
+            strings(troparia),
+            vec!["Galatians 4:4-7".to_string(), "Matthew 2:1-12".to_string()],
+        ).unwrap()
+    }
+
+    fn render(template: &str, day: &OrthoCalendarData) -> String {
+        Template::parse(template).unwrap().render(day)
+    }
+
+    fn error(template: &str) -> String {
+        Template::parse(template).unwrap_err().to_string()
+    }
+
+    /// The DESCRIPTION as built before templates existed
+    fn legacy_description(day: &OrthoCalendarData) -> String {
+        let clean_troparia: Vec<String> = day.troparia.iter()
+            .map(|t| {
+                if t.starts_with("Troparia") {
+                    t.replacen("Troparia", "", 1).trim().to_string()
+                } else {
+                    t.to_string()
+                }
+            })
+            .collect();
+
+        let mut heading = format!("({})", day.julian_date);
+        for festal_day in &day.festal {
+            heading.push_str(&format!("\n{}", festal_day));
+        }
+        for coincidence in &day.coincidences {
+            heading.push_str(&format!("\n{}", coincidence));
+        }
+        for observance in &day.observances {
+            heading.push_str(&format!("\n{}", observance));
+        }
+        if let Some(liturgy) = &day.liturgy {
+            heading.push_str(&format!("\n{}", liturgy));
+        }
+        let notes = if day.liturgical_notes.is_empty() {
+            String::new()
+        } else {
+            format!("\n\nNotes:\n{}", day.liturgical_notes)
+        };
+        format!(
+            "{}{}\n\nSaints:\n{}\n\nTroparia:\n{}\n\nScripture:\n{}",
+            heading,
+            notes,
+            sanitize_text(&day.lives.join("\n")),
+            sanitize_text(&clean_troparia.join("\n")).replace('\n', "\n\n"),
+            sanitize_text(&day.scripture.join("\n"))
+        )
+    }
+
+    #[test]
+    fn text_and_fields() {
+        let day = day("", &[], &[]);
+        assert_eq!(render("", &day), "");
+        assert_eq!(render("Plain text", &day), "Plain text");
+        assert_eq!(render("{summary} ({julian_date})", &day), "Nativity of Christ (December 25, 2024)");
+        assert_eq!(render("{ date }", &day), "2025-01-07");
+        assert_eq!(render("{scripture}", &day), "Galatians 4:4-7\nMatthew 2:1-12");
+        assert_eq!(render("{liturgical_notes}", &day), "");
+    }
+
+    #[test]
+    fn sections() {
+        let with_notes = day("Fast-free", &[], &[]);
+        let without_notes = day("", &[], &[]);
+        let template = "{#liturgical_notes}Notes: {liturgical_notes}{/liturgical_notes}{^liturgical_notes}No notes{/liturgical_notes}";
+        assert_eq!(render(template, &with_notes), "Notes: Fast-free");
+        assert_eq!(render(template, &without_notes), "No notes");
+    }
+
+    #[test]
+    fn nested_sections() {
+        let template = "{#summary}[{summary}{#lives}: {lives}{/lives}{^lives}{^troparia} (no texts){/troparia}{/lives}]{/summary}";
+        assert_eq!(render(template, &day("", &["St. Anastasia"], &[])), "[Nativity of Christ: St. Anastasia]");
+        assert_eq!(render(template, &day("", &[], &[])), "[Nativity of Christ (no texts)]");
+        assert_eq!(render(template, &day("", &[], &["Thy Nativity, O Christ our God"])), "[Nativity of Christ]");
+    }
+
+    #[test]
+    fn escaped_braces() {
+        let day = day("", &[], &[]);
+        assert_eq!(render("{{summary}} is {summary}", &day), "{summary} is Nativity of Christ");
+        assert_eq!(render("}}{{", &day), "}{");
+    }
+
+    #[test]
+    fn errors() {
+        assert!(error("{sumary}").starts_with("Unknown template field {sumary}"));
+        assert!(error("{#lives}").contains("{#lives} is never closed with {/lives}"));
+        assert!(error("{^lives}").contains("{^lives} is never closed with {/lives}"));
+        assert!(error("{#lives}{/troparia}").contains("Unexpected {/troparia}"));
+        assert!(error("{/lives}").contains("Unexpected {/lives}"));
+        assert!(error("{summary").contains("Unclosed '{'"));
+        assert!(error("summary}").contains("Unmatched '}'"));
+    }
+
+    #[test]
+    fn default_description_matches_the_old_layout() {
+        let template = Template::parse(DEFAULT_DESCRIPTION).unwrap();
+        let scraped = [
+            day("", &[], &[]),
+            day("Fast-free", &["St. Anastasia", "St. Eugenia"], &["Troparia Thy Nativity, O Christ our God", "Kontakion Today the Virgin"]),
+        ];
+        let computed = compute_year_data(2025).unwrap().into_values();
+        for day in scraped.into_iter().chain(computed) {
+            assert_eq!(template.render(&day), legacy_description(&day), "{}", day.date);
+        }
+    }
+}
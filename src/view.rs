@@ -0,0 +1,42 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use crate::json::OrthoCalendarData;
+
+fn print_section(title: &str, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+    println!();
+    println!("{}:", title);
+    for line in lines {
+        println!("  {}", line);
+    }
+}
+
+/// Prints a single day's calendar data to the terminal
+pub fn print_day(day_data: &OrthoCalendarData) -> Result<()> {
+    let date = NaiveDate::parse_from_str(&day_data.date, "%Y-%m-%d")?;
+
+    println!("{} ({})", date.format("%A, %B %-d, %Y"), day_data.julian_date);
+    println!("{}", day_data.summary);
+    for festal_day in &day_data.festal {
+        println!("{}", festal_day);
+    }
+    for coincidence in &day_data.coincidences {
+        println!("{}", coincidence);
+    }
+    for observance in &day_data.observances {
+        println!("{}", observance);
+    }
+    if let Some(liturgy) = &day_data.liturgy {
+        println!("{}", liturgy);
+    }
+    if !day_data.liturgical_notes.is_empty() {
+        println!("{}", day_data.liturgical_notes);
+    }
+
+    print_section("Saints", &day_data.lives);
+    print_section("Troparia", &day_data.troparia);
+    print_section("Scripture", &day_data.scripture);
+    Ok(())
+}